@@ -0,0 +1,173 @@
+use std::fmt::Display;
+use rand::{seq::SliceRandom, Rng};
+use serde::{Deserialize, Serialize};
+use crate::{grade::{grade, Grade, Leniency}, search::{Category, Item, Language, Search, Uid, VerbForms}};
+
+#[derive(Clone, Copy, Serialize, Deserialize, Debug, PartialEq)]
+pub enum Tense {
+    Present,
+    PasseCompose,
+    Imparfait,
+}
+
+pub const TENSES: [Tense; 3] = [Tense::Present, Tense::PasseCompose, Tense::Imparfait];
+
+impl Display for Tense {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", match self {
+            Self::Present => "Présent",
+            Self::PasseCompose => "Passé composé",
+            Self::Imparfait => "Imparfait",
+        })
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum VerbFilter {
+    All,
+    Regular,
+    Irregular,
+}
+
+impl Display for VerbFilter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", match self {
+            Self::All => "All verbs",
+            Self::Regular => "Regular verbs",
+            Self::Irregular => "Irregular verbs",
+        })
+    }
+}
+
+impl VerbFilter {
+    fn accepts(&self, forms: &VerbForms) -> bool {
+        match self {
+            Self::All => true,
+            Self::Regular => matches!(forms, VerbForms::Regular(..)),
+            Self::Irregular => matches!(forms, VerbForms::Irregular(..)),
+        }
+    }
+}
+
+pub const PERSONS: [&str; 6] = ["je", "tu", "il/elle/on", "nous", "vous", "ils/elles"];
+const AVOIR: [&str; 6] = ["ai", "as", "a", "avons", "avez", "ont"];
+const ETRE: [&str; 6] = ["suis", "es", "est", "sommes", "êtes", "sont"];
+// Verbs that take être in the passé composé, the forms themselves do not say which auxiliary to use
+const ETRE_VERBS: [&str; 18] = ["aller", "arriver", "descendre", "devenir", "entrer", "monter", "mourir", "naître", "partir", "rentrer", "rester", "retourner", "revenir", "sortir", "tomber", "venir", "décéder", "parvenir"];
+
+fn starts_with_vowel(string: &str) -> bool {
+    string.to_lowercase().starts_with(|c| "aeiouyhâàéèêîôûœ".contains(c))
+}
+
+pub fn uses_etre(infinitive: &str) -> bool {
+    ETRE_VERBS.contains(&infinitive)
+}
+
+// The six persons of the tense, or None if the verb is missing any of them
+pub fn conjugate(infinitive: &str, forms: &VerbForms, tense: Tense) -> Option<[String; 6]> {
+    let (VerbForms::Regular(je, tu, il, nous, vous, ils, pc, imp_je, imp_tu, imp_il, imp_nous, imp_vous, imp_ils) | VerbForms::Irregular(je, tu, il, nous, vous, ils, pc, imp_je, imp_tu, imp_il, imp_nous, imp_vous, imp_ils)) = forms;
+    let persons = match tense {
+        Tense::Present => [je, tu, il, nous, vous, ils].map(|x| x.clone()),
+        Tense::Imparfait => [imp_je, imp_tu, imp_il, imp_nous, imp_vous, imp_ils].map(|x| x.clone()),
+        Tense::PasseCompose if uses_etre(infinitive) => {
+            let plural = format!("{}s", pc);
+            [0, 1, 2, 3, 4, 5].map(|i| format!("{} {}", ETRE[i], if i < 3 { pc } else { &plural }))
+        }
+        Tense::PasseCompose => AVOIR.map(|x| format!("{} {}", x, pc)),
+    };
+    if persons.iter().any(|x| x.trim().is_empty() || x.ends_with(' ')) {
+        return None;
+    }
+    Some(persons)
+}
+
+fn tenses(infinitive: &str, forms: &VerbForms, tenses: &[Tense]) -> Vec<Tense> {
+    tenses.iter().copied().filter(|x| conjugate(infinitive, forms, *x).is_some()).collect()
+}
+
+#[derive(PartialEq)]
+pub struct Cell {
+    pub person: String,
+    pub expected: String,
+    // The form differs from what the regular pattern of the infinitive would give
+    pub irregular: bool,
+    pub answer: String,
+    pub grade: Option<Grade>,
+}
+
+#[derive(PartialEq)]
+pub struct ConjugationDrill {
+    uids: Vec<Uid>,
+    tenses: Vec<Tense>,
+    pub current: Uid,
+    pub infinitive: String,
+    pub tense: Tense,
+    pub cells: Vec<Cell>,
+    pub checked: bool,
+    pub answered: u32,
+    pub correct: u32,
+}
+
+impl ConjugationDrill {
+    pub fn new(search: &Search, uids: Vec<Uid>, tenses: Vec<Tense>, filter: VerbFilter, rng: &mut impl Rng) -> Option<Self> {
+        let uids: Vec<Uid> = uids.into_iter().filter(|uid| match search.get_item(*uid) {
+            Some(Item { category: Category::Verb(infinitive, forms), .. }) => filter.accepts(&forms) && !self::tenses(&infinitive, &forms, &tenses).is_empty(),
+            _ => false,
+        }).collect();
+        let mut drill = Self { current: *uids.first()?, uids, tenses, infinitive: String::new(), tense: Tense::Present, cells: vec![], checked: false, answered: 0, correct: 0 };
+        drill.next(search, rng).then_some(drill)
+    }
+
+    // Picks another verb and tense, returns false if there are no verbs left
+    pub fn next(&mut self, search: &Search, rng: &mut impl Rng) -> bool {
+        let previous = self.current;
+        loop {
+            if self.uids.is_empty() {
+                return false;
+            }
+            let mut uid = *self.uids.choose(rng).unwrap();
+            if uid == previous && self.uids.len() > 1 {
+                uid = *self.uids.choose(rng).unwrap();
+            }
+            let Some(Item { category: Category::Verb(infinitive, forms), .. }) = search.get_item(uid) else {
+                self.uids.retain(|x| *x != uid);
+                continue;
+            };
+            let Some(tense) = tenses(&infinitive, &forms, &self.tenses).choose(rng).copied() else {
+                self.uids.retain(|x| *x != uid);
+                continue;
+            };
+            let persons = conjugate(&infinitive, &forms, tense).unwrap();
+            let (je, tu, il, nous, vous, ils, pc, imp_je, imp_tu, imp_il, imp_nous, imp_vous, imp_ils) = VerbForms::gen_from_regular(&infinitive);
+            let regular = VerbForms::Regular(je, tu, il, nous, vous, ils, pc, imp_je, imp_tu, imp_il, imp_nous, imp_vous, imp_ils);
+            let regular = conjugate(&infinitive, &regular, tense);
+            self.cells = persons.into_iter().enumerate().map(|(i, expected)| Cell {
+                person: if i == 0 && starts_with_vowel(&expected) { "j'".to_string() } else { PERSONS[i].to_string() },
+                irregular: matches!(forms, VerbForms::Irregular(..)) && regular.as_ref().is_some_and(|x| x[i] != expected),
+                expected,
+                answer: String::new(),
+                grade: None,
+            }).collect();
+            self.current = uid;
+            self.infinitive = infinitive;
+            self.tense = tense;
+            self.checked = false;
+            return true;
+        }
+    }
+
+    // Grades every cell and returns the worst grade and whether the whole table was accepted
+    pub fn check(&mut self, item: &Item, leniency: &Leniency) -> (Grade, bool) {
+        for cell in &mut self.cells {
+            cell.grade = Some(grade(&cell.answer, std::slice::from_ref(&cell.expected), item, &Language::French));
+        }
+        let worst = self.cells.iter().filter_map(|x| x.grade).max().unwrap_or(Grade::Wrong);
+        let correct = self.cells.iter().all(|x| x.grade.is_some_and(|x| leniency.accepts(x)));
+        self.checked = true;
+        self.answered += 1;
+        if correct {
+            self.correct += 1;
+        }
+        (worst, correct)
+    }
+}
@@ -1,4 +1,4 @@
-use crate::search::{Item, Language, Query, Search};
+use crate::{phonetics, search::{Item, Language, Query, Search, Uid}};
 use lazy_static::lazy_static;
 use regex::Regex;
 
@@ -10,18 +10,78 @@ pub struct Part {
     pub string: String,
     pub matched: Vec<(String, Item)>,
     pub chosen: usize,
-    pub sure: bool
+    pub sure: bool,
+    // Written with an apostrophe in the text, like the l' in l'ami
+    pub elided: bool,
 }
 
 pub fn explain(string: &str, search: &Search) -> Vec<Part> {
     let mut parts = vec![];
     for cap in WORD_REGEX.captures_iter(string) {
         let mut cap_string = cap.get(0).unwrap().as_str().to_string();
-        if cap_string.ends_with('\'') {
+        let elided = cap_string.ends_with('\'');
+        if elided {
             cap_string.replace_range((cap_string.len()-1)..cap_string.len(), "e");
         }
         let result = search.search_best_answers(&Query::new(&cap_string, &Language::French, 0b1111111111111111, cap_string.ends_with('\'')));
-        parts.push(Part { string: cap_string, matched: result.0.clone(), sure: result.1 == 0, chosen: 0 });
+        parts.push(Part { string: cap_string, matched: result.0.clone(), sure: result.1 == 0, chosen: 0, elided });
     }
     parts
-}
\ No newline at end of file
+}
+
+// The best match for every word of the sentence, in order and without repeats
+pub fn linked_words(string: &str, search: &Search) -> Vec<Uid> {
+    let mut uids = vec![];
+    for part in explain(string, search) {
+        if let Some((_, item)) = part.matched.get(part.chosen) {
+            if !uids.contains(&item.uid) {
+                uids.push(item.uid);
+            }
+        }
+    }
+    uids
+}
+
+// The text split into words and what lies between them, with the words that are one of the forms marked
+pub fn mark_words(text: &str, forms: &[String]) -> Vec<(String, bool)> {
+    let mut parts: Vec<(String, bool)> = vec![];
+    let mut current = String::new();
+    for c in text.chars().chain(std::iter::once(' ')) {
+        if c.is_alphabetic() == current.chars().next().is_some_and(|x| x.is_alphabetic()) || current.is_empty() {
+            current.push(c);
+            continue;
+        }
+        let marked = forms.contains(&current.to_lowercase());
+        parts.push((std::mem::take(&mut current), marked));
+        current.push(c);
+    }
+    current.pop();
+    if !current.is_empty() {
+        parts.push((current, false));
+    }
+    parts
+}
+
+pub fn forms(item: &Item) -> Vec<String> {
+    let (required, optional) = item.category.forms();
+    required.into_iter().chain(optional).map(|(_, form)| form.to_lowercase()).filter(|x| !x.is_empty()).collect()
+}
+
+// How neighbouring words change each other's pronunciation
+pub fn hints(parts: &[Part]) -> Vec<String> {
+    let mut hints = vec![];
+    for pair in parts.windows(2) {
+        let (first, second) = (&pair[0], &pair[1]);
+        let category = first.matched.get(first.chosen).map(|(_, item)| &item.category);
+        if first.elided {
+            // The apostrophe was read as an e, but the matched word may be la
+            let full = first.matched.get(first.chosen).map_or(&first.string, |(string, _)| string);
+            hints.push(format!("Elision: {}' is {} with the vowel dropped before {}", first.string.trim_end_matches('e'), full, second.string));
+        } else if phonetics::elides(&first.string) && phonetics::starts_with_vowel_sound(&second.string) {
+            hints.push(format!("Elision: {} {} is usually written {}'{}", first.string, second.string, first.string.trim_end_matches(['e', 'a']), second.string));
+        } else if let Some(hint) = phonetics::liaison(&first.string, category, &second.string) {
+            hints.push(hint);
+        }
+    }
+    hints
+}
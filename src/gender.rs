@@ -0,0 +1,150 @@
+use rand::{seq::SliceRandom, Rng};
+use crate::search::{Category, Gender, Item, Noun, ProperOrCommon, Search, Uid};
+
+const ENDINGS: [(&str, Gender); 36] = [
+    ("tion", Gender::Female),
+    ("sion", Gender::Female),
+    ("xion", Gender::Female),
+    ("té", Gender::Female),
+    ("ette", Gender::Female),
+    ("ance", Gender::Female),
+    ("ence", Gender::Female),
+    ("ure", Gender::Female),
+    ("ade", Gender::Female),
+    ("ude", Gender::Female),
+    ("ée", Gender::Female),
+    ("ie", Gender::Female),
+    ("ise", Gender::Female),
+    ("esse", Gender::Female),
+    ("ière", Gender::Female),
+    ("elle", Gender::Female),
+    ("euse", Gender::Female),
+    ("ine", Gender::Female),
+    ("age", Gender::Male),
+    ("ment", Gender::Male),
+    ("eau", Gender::Male),
+    ("isme", Gender::Male),
+    ("oir", Gender::Male),
+    ("ier", Gender::Male),
+    ("et", Gender::Male),
+    ("ail", Gender::Male),
+    ("eil", Gender::Male),
+    ("al", Gender::Male),
+    ("at", Gender::Male),
+    ("ou", Gender::Male),
+    ("in", Gender::Male),
+    ("on", Gender::Male),
+    ("er", Gender::Male),
+    ("é", Gender::Male),
+    ("ème", Gender::Male),
+    ("o", Gender::Male),
+];
+
+// The longest ending of the noun that usually decides its gender
+pub fn rule(noun: &str) -> Option<(&'static str, Gender)> {
+    let noun = noun.to_lowercase();
+    ENDINGS.iter()
+        .filter(|(ending, _)| noun.ends_with(ending) && noun.len() > ending.len())
+        .max_by_key(|(ending, _)| ending.chars().count())
+        .map(|(ending, gender)| (*ending, gender.clone()))
+}
+
+pub fn explanation(noun: &Noun) -> Option<String> {
+    let (ending, gender) = rule(&noun.singular)?;
+    let kind = match gender {
+        Gender::Male => "masculine",
+        Gender::Female => "feminine",
+    };
+    if gender == noun.gender {
+        Some(format!("Nouns ending in -{} are usually {}.", ending, kind))
+    } else {
+        Some(format!("Nouns ending in -{} are usually {}, but '{}' is an exception.", ending, kind, noun.singular))
+    }
+}
+
+fn starts_with_vowel(string: &str) -> bool {
+    string.to_lowercase().starts_with(|c| "aeiouyhâàéèêîôûœ".contains(c))
+}
+
+#[derive(PartialEq)]
+pub struct GenderDrill {
+    uids: Vec<Uid>,
+    to_repeat: Vec<Uid>,
+    pub current: Uid,
+    definite: bool,
+    pub streak: u32,
+    pub answered: u32,
+    pub correct: u32,
+    // The previous noun with its article, whether it was right and the ending rule it follows or breaks
+    pub feedback: Option<(String, bool, Option<String>)>,
+}
+
+impl GenderDrill {
+    pub fn new(uids: Vec<Uid>, rng: &mut impl Rng) -> Option<Self> {
+        let current = *uids.choose(rng)?;
+        Some(Self { uids, to_repeat: vec![], current, definite: rng.gen_bool(0.5), streak: 0, answered: 0, correct: 0, feedback: None })
+    }
+
+    // Le and la are elided before a vowel, so those nouns are always asked with un and une
+    pub fn articles(&self, noun: &Noun) -> (&'static str, &'static str) {
+        if self.definite && !starts_with_vowel(&noun.singular) {
+            ("le", "la")
+        } else {
+            ("un", "une")
+        }
+    }
+
+    pub fn answer(&mut self, noun: &Noun, gender: Gender, rng: &mut impl Rng) -> bool {
+        let correct = gender == noun.gender;
+        let (male, female) = self.articles(noun);
+        let article = if noun.gender == Gender::Male { male } else { female };
+        self.answered += 1;
+        if correct {
+            self.correct += 1;
+            self.streak += 1;
+        } else {
+            self.streak = 0;
+            self.to_repeat.push(self.current);
+        }
+        self.feedback = Some((format!("{} {}", article, noun.singular), correct, explanation(noun)));
+        self.next(rng);
+        correct
+    }
+
+    pub fn next(&mut self, rng: &mut impl Rng) {
+        self.definite = rng.gen_bool(0.5);
+        if !self.to_repeat.is_empty() && rng.gen_bool(0.3) {
+            let index = rng.gen_range(0..self.to_repeat.len());
+            self.current = self.to_repeat.swap_remove(index);
+            return;
+        }
+        let previous = self.current;
+        for _ in 0..3 {
+            self.current = *self.uids.choose(rng).unwrap();
+            if self.current != previous {
+                break;
+            }
+        }
+    }
+
+    pub fn remove(&mut self, uid: Uid, rng: &mut impl Rng) -> bool {
+        self.uids.retain(|x| *x != uid);
+        self.to_repeat.retain(|x| *x != uid);
+        if self.uids.is_empty() {
+            return false;
+        }
+        self.next(rng);
+        true
+    }
+}
+
+pub fn drillable(item: &Item) -> bool {
+    matches!(&item.category, Category::Noun(noun) if noun.proper == ProperOrCommon::Common)
+}
+
+pub fn noun(search: &Search, uid: Uid) -> Option<Noun> {
+    match search.get_item(uid)?.category {
+        Category::Noun(noun) => Some(noun),
+        _ => None,
+    }
+}
@@ -0,0 +1,249 @@
+use levenshtein::levenshtein;
+use serde::{Deserialize, Serialize};
+use crate::search::{Item, Language};
+
+#[derive(Clone, Copy, Serialize, Deserialize, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Grade {
+    Correct,
+    CaseOrSpacing,
+    Accent,
+    Elision,
+    Gender,
+    Form,
+    Wrong,
+}
+
+impl Grade {
+    pub fn feedback(&self) -> &str {
+        match self {
+            Self::Correct => "Correct.",
+            Self::CaseOrSpacing => "Check the capitalization and spacing.",
+            Self::Accent => "Check the accents.",
+            Self::Elision => "Remember the elision.",
+            Self::Gender => "That is the form for the other gender.",
+            Self::Form => "That is another form of the same word.",
+            Self::Wrong => "That is not the right word.",
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug, Default)]
+pub struct Leniency {
+    pub case: bool,
+    pub accent: bool,
+    pub elision: bool,
+}
+
+impl Leniency {
+    pub fn accepts(&self, grade: Grade) -> bool {
+        match grade {
+            Grade::Correct => true,
+            Grade::CaseOrSpacing => self.case,
+            Grade::Accent => self.accent,
+            Grade::Elision => self.elision,
+            Grade::Gender | Grade::Form | Grade::Wrong => false,
+        }
+    }
+}
+
+fn normalize(string: &str) -> String {
+    string.split_whitespace().collect::<Vec<&str>>().join(" ").to_lowercase()
+}
+
+fn strip_accents(string: &str) -> String {
+    string.chars().map(|c| match c {
+        'à' | 'â' | 'ä' => 'a',
+        'é' | 'è' | 'ê' | 'ë' => 'e',
+        'î' | 'ï' => 'i',
+        'ô' | 'ö' => 'o',
+        'ù' | 'û' | 'ü' => 'u',
+        'ÿ' => 'y',
+        'ç' => 'c',
+        _ => c,
+    }).collect()
+}
+
+const ELIDED: [&str; 10] = ["le", "la", "je", "me", "te", "se", "de", "ne", "ce", "que"];
+
+// Contracts "le arbre" into "l'arbre", treating every h as silent
+fn elide(string: &str) -> String {
+    let words: Vec<&str> = string.split(' ').collect();
+    let mut elided = String::new();
+    for (i, word) in words.iter().enumerate() {
+        elided += word;
+        if let Some(next) = words.get(i + 1) {
+            if ELIDED.contains(word) && next.starts_with(|c| "aeiouyhâàéèêîôûœ".contains(c)) {
+                elided.pop();
+                elided.push('\'');
+            } else {
+                elided.push(' ');
+            }
+        }
+    }
+    elided
+}
+
+fn genderless(name: &str) -> String {
+    name.split(' ').filter(|x| *x != "male" && *x != "female").collect::<Vec<&str>>().join(" ")
+}
+
+fn grade_against(answer: &str, expected: &str, item: &Item, language: &Language) -> Grade {
+    if answer == expected {
+        return Grade::Correct;
+    }
+    let (answer, expected) = (normalize(answer), normalize(expected));
+    if answer == expected {
+        return Grade::CaseOrSpacing;
+    }
+    // Å, ä and ö are letters of their own in swedish, so only french answers can get an accent wrong
+    if *language != Language::French {
+        return Grade::Wrong;
+    }
+    if strip_accents(&answer) == strip_accents(&expected) {
+        return Grade::Accent;
+    }
+    if elide(&answer) == expected {
+        return Grade::Elision;
+    }
+    // Check whether the answer is one of the other stored forms of the same item
+    let (required, optional) = item.category.forms();
+    let forms: Vec<(&str, String)> = required.into_iter().chain(optional).map(|(name, form)| (name, normalize(form))).collect();
+    let expected_name = forms.iter().find(|(_, form)| *form == expected).map(|(name, _)| *name);
+    match forms.iter().find(|(_, form)| *form == answer) {
+        Some((name, _)) if expected_name.is_some_and(|x| x != *name && genderless(x) == genderless(name)) => Grade::Gender,
+        Some(_) => Grade::Form,
+        None => Grade::Wrong,
+    }
+}
+
+// Grades the answer against the closest of the accepted answers
+pub fn grade(answer: &str, accepted: &[String], item: &Item, language: &Language) -> Grade {
+    accepted.iter().map(|expected| grade_against(answer, expected, item, language)).min().unwrap_or(Grade::Wrong)
+}
+
+pub fn closeness(answer: &str, expected: &str) -> &'static str {
+    match levenshtein(expected, answer) {
+        0..=2 => "You were close.",
+        3 => "That's almost close.",
+        _ => "You need to practice this more.",
+    }
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub enum Token {
+    Correct(String),
+    // The answered word and the expected one
+    Misspelled(String, String),
+    Missing(String),
+    Extra(String),
+}
+
+impl Token {
+    fn is_error(&self) -> bool {
+        !matches!(self, Self::Correct(_))
+    }
+}
+
+// Splits into words without punctuation, keeping elided words like "l'" as their own word
+fn tokenize(string: &str) -> Vec<String> {
+    let mut tokens = vec![];
+    for word in string.replace('’', "'").split_whitespace() {
+        let mut rest = word;
+        while let Some(i) = rest.find('\'') {
+            tokens.push(rest[..=i].to_string());
+            rest = &rest[i + 1..];
+        }
+        tokens.push(rest.to_string());
+    }
+    tokens.iter()
+        .map(|x| x.trim_start_matches(|c: char| !c.is_alphanumeric()).trim_end_matches(|c: char| !c.is_alphanumeric() && c != '\''))
+        .filter(|x| !x.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+fn join(tokens: &[String]) -> String {
+    let mut joined = String::new();
+    for token in tokens {
+        if !joined.is_empty() && !joined.ends_with('\'') {
+            joined.push(' ');
+        }
+        joined += &token.to_lowercase();
+    }
+    joined
+}
+
+fn similar(answer: &str, expected: &str) -> bool {
+    let (answer, expected) = (answer.to_lowercase(), expected.to_lowercase());
+    strip_accents(&answer) == strip_accents(&expected) || levenshtein(&answer, &expected) <= (expected.chars().count() / 3).max(1)
+}
+
+// Aligns the answered words with the expected ones with the fewest missing, extra and misspelled words
+pub fn align(answer: &str, expected: &str) -> Vec<Token> {
+    let (answer, expected) = (tokenize(answer), tokenize(expected));
+    let (n, m) = (answer.len(), expected.len());
+    let mut cost = vec![vec![0; m + 1]; n + 1];
+    for (i, row) in cost.iter_mut().enumerate() {
+        row[0] = i;
+    }
+    for (j, cell) in cost[0].iter_mut().enumerate() {
+        *cell = j;
+    }
+    for i in 1..=n {
+        for j in 1..=m {
+            let substitute = if answer[i - 1].to_lowercase() == expected[j - 1].to_lowercase() {
+                cost[i - 1][j - 1]
+            } else if similar(&answer[i - 1], &expected[j - 1]) {
+                cost[i - 1][j - 1] + 1
+            } else {
+                usize::MAX
+            };
+            cost[i][j] = substitute.min(cost[i - 1][j] + 1).min(cost[i][j - 1] + 1);
+        }
+    }
+    let mut tokens = vec![];
+    let (mut i, mut j) = (n, m);
+    while i > 0 || j > 0 {
+        if i > 0 && j > 0 && answer[i - 1].to_lowercase() == expected[j - 1].to_lowercase() && cost[i][j] == cost[i - 1][j - 1] {
+            tokens.push(Token::Correct(expected[j - 1].clone()));
+            (i, j) = (i - 1, j - 1);
+        } else if i > 0 && j > 0 && similar(&answer[i - 1], &expected[j - 1]) && cost[i][j] == cost[i - 1][j - 1] + 1 {
+            tokens.push(Token::Misspelled(answer[i - 1].clone(), expected[j - 1].clone()));
+            (i, j) = (i - 1, j - 1);
+        } else if j > 0 && cost[i][j] == cost[i][j - 1] + 1 {
+            tokens.push(Token::Missing(expected[j - 1].clone()));
+            j -= 1;
+        } else {
+            tokens.push(Token::Extra(answer[i - 1].clone()));
+            i -= 1;
+        }
+    }
+    tokens.reverse();
+    tokens
+}
+
+// Grades a sentence word by word, so punctuation and capitalization never count as mistakes
+pub fn grade_sentence(answer: &str, accepted: &[String], item: &Item, language: &Language) -> (Grade, Vec<Token>) {
+    accepted.iter().map(|expected| {
+        let tokens = align(answer, expected);
+        let grade = if tokens.iter().any(Token::is_error) {
+            grade_against(&join(&tokenize(answer)), &join(&tokenize(expected)), item, language)
+        } else {
+            Grade::Correct
+        };
+        (grade, tokens)
+    }).min_by_key(|(grade, tokens)| (*grade, tokens.iter().filter(|x| x.is_error()).count())).unwrap_or((Grade::Wrong, vec![]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::search::Category;
+
+    #[test]
+    fn swedish_letters_are_not_accents() {
+        let item = Item::new(Some("här".to_string()), None, Category::Adverb("déjà".to_string()), 1, vec![], vec![], None);
+        assert_eq!(grade("har", &["här".to_string()], &item, &Language::Swedish), Grade::Wrong);
+        assert_eq!(grade("deja", &["déjà".to_string()], &item, &Language::French), Grade::Accent);
+    }
+}
@@ -0,0 +1,153 @@
+use crate::{media::Media, practice::{PracticeGroup, PracticeGroupCollection, QuestionTemplate}, search::{Item, Search, Uid}};
+
+const MAX_HISTORY: usize = 100;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Target {
+    Words,
+    Sentences,
+}
+
+impl Target {
+    fn search<'a>(&self, words: &'a mut Search, sentences: &'a mut Search) -> &'a mut Search {
+        match self {
+            Self::Words => words,
+            Self::Sentences => sentences,
+        }
+    }
+
+    fn media_name(&self, uid: Uid) -> String {
+        match self {
+            Self::Words => QuestionTemplate::Word(uid).media_name(),
+            Self::Sentences => QuestionTemplate::Sentence(uid).media_name(),
+        }
+    }
+}
+
+pub enum Change {
+    AddItem(Target, Box<Item>),
+    EditItem(Target, Box<Item>, Box<Item>),
+    RemoveItem(Target, Box<Item>),
+    Groups(Vec<PracticeGroup>, Vec<PracticeGroup>),
+}
+
+impl Change {
+    fn apply(&self, undo: bool, words: &mut Search, sentences: &mut Search, groups: &mut PracticeGroupCollection) {
+        match self {
+            Self::AddItem(target, item) | Self::RemoveItem(target, item) => {
+                let search = target.search(words, sentences);
+                let adding = matches!(self, Self::AddItem(..)) != undo;
+                if adding {
+                    let _ = search.add_item((**item).clone());
+                } else {
+                    let _ = search.remove_item(item.uid);
+                }
+            }
+            Self::EditItem(target, old, new) => {
+                let search = target.search(words, sentences);
+                let item = if undo { old } else { new };
+                let _ = search.edit_item(item.uid, (**item).clone());
+            }
+            Self::Groups(old, new) => {
+                groups.groups = if undo { old.clone() } else { new.clone() };
+            }
+        }
+    }
+
+    // An item that isn't there after the change has its files in the trash.
+    // Files that can't be moved or removed are only wasted space.
+    fn move_media(&self, undo: bool, media: &Media) {
+        if let Self::AddItem(target, item) | Self::RemoveItem(target, item) = self {
+            let name = target.media_name(item.uid);
+            let _ = if matches!(self, Self::AddItem(..)) != undo { media.restore(&name) } else { media.trash(&name) };
+        }
+    }
+
+    // Called when the change can't be undone or redone anymore
+    fn forget_media(&self, undone: bool, media: &Media) {
+        if let Self::AddItem(target, item) | Self::RemoveItem(target, item) = self {
+            if matches!(self, Self::AddItem(..)) == undone {
+                let _ = media.forget(&target.media_name(item.uid));
+            }
+        }
+    }
+}
+
+pub struct Edit {
+    pub description: String,
+    changes: Vec<Change>,
+}
+
+impl Edit {
+    fn forget_media(&self, undone: bool, media: &Media) {
+        for change in &self.changes {
+            change.forget_media(undone, media);
+        }
+    }
+}
+
+// Every change that removes an item goes through here, which keeps its recording and picture until the removal is final
+pub struct History {
+    undo: Vec<Edit>,
+    redo: Vec<Edit>,
+    media: Media,
+}
+
+impl History {
+    pub fn new(media: Media) -> Self {
+        Self { undo: vec![], redo: vec![], media }
+    }
+
+    // The changes have already been made to the items
+    pub fn record(&mut self, description: String, changes: Vec<Change>) {
+        if changes.is_empty() {
+            return;
+        }
+        for change in &changes {
+            change.move_media(false, &self.media);
+        }
+        self.undo.push(Edit { description, changes });
+        if self.undo.len() > MAX_HISTORY {
+            self.undo.remove(0).forget_media(false, &self.media);
+        }
+        for edit in self.redo.drain(..) {
+            edit.forget_media(true, &self.media);
+        }
+    }
+
+    pub fn undo(&mut self, words: &mut Search, sentences: &mut Search, groups: &mut PracticeGroupCollection) -> bool {
+        match self.undo.pop() {
+            Some(edit) => {
+                for change in edit.changes.iter().rev() {
+                    change.apply(true, words, sentences, groups);
+                    change.move_media(true, &self.media);
+                }
+                self.redo.push(edit);
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn redo(&mut self, words: &mut Search, sentences: &mut Search, groups: &mut PracticeGroupCollection) -> bool {
+        match self.redo.pop() {
+            Some(edit) => {
+                for change in &edit.changes {
+                    change.apply(false, words, sentences, groups);
+                    change.move_media(false, &self.media);
+                }
+                self.undo.push(edit);
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn undo_edits(&self) -> &[Edit] {
+        &self.undo
+    }
+
+    pub fn redo_edits(&self) -> &[Edit] {
+        &self.redo
+    }
+}
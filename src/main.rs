@@ -4,16 +4,46 @@ mod practice;
 mod explain;
 mod utils;
 mod sentence;
+mod history;
+mod merge;
+mod validate;
+mod grade;
+mod gender;
+mod conjugation;
+mod speech;
+mod media;
+mod phonetics;
 
 const WORDS_FILE: &str = "words.bin";
 const SENTENCES_FILE: &str = "sentences.bin";
 const PRACTICE_FILE: &str = "practice.bin";
+const SPEECH_CACHE: &str = "speech";
+
+fn load() -> Result<(search::Search, search::Search, practice::PracticeGroupCollection), String> {
+    let (engine_words, word_uids) = search::Search::load_or_new(WORDS_FILE)?;
+    let (engine_sentences, sentence_uids) = search::Search::load_or_new(SENTENCES_FILE)?;
+    let none = search::LegacyUids::new();
+    let practice = practice::PracticeGroupCollection::load_or_new(PRACTICE_FILE, word_uids.as_ref().unwrap_or(&none), sentence_uids.as_ref().unwrap_or(&none))?;
+    // Items from before uids were random got new ones, so the files are saved together before any of them can be saved alone
+    if word_uids.is_some() || sentence_uids.is_some() {
+        engine_words.save(WORDS_FILE);
+        engine_sentences.save(SENTENCES_FILE);
+        practice.save(PRACTICE_FILE);
+    }
+    Ok((engine_words, engine_sentences, practice))
+}
 
 fn main() {
-    let engine_words = search::Search::load_or_new(WORDS_FILE);
-    let engine_sentences = search::Search::load_or_new(SENTENCES_FILE);
-    let practice = practice::PracticeGroupCollection::load_or_new(PRACTICE_FILE);
-    ui::run(engine_words, engine_sentences, practice, WORDS_FILE.to_string(), SENTENCES_FILE.to_string(), PRACTICE_FILE.to_string()).unwrap();
+    // Starting without the files would overwrite them on the next save
+    let (engine_words, engine_sentences, practice) = match load() {
+        Ok(data) => data,
+        Err(error) => {
+            eprintln!("{}", error);
+            std::process::exit(1);
+        }
+    };
+    let pronouncer = speech::Pronouncer::new(std::sync::Arc::new(speech::Espeak::default()), SPEECH_CACHE);
+    ui::run(engine_words, engine_sentences, practice, WORDS_FILE.to_string(), SENTENCES_FILE.to_string(), PRACTICE_FILE.to_string(), pronouncer).unwrap();
     
     // let engine_words = search::Search::from_old(search::SearchOld::load_or_new(WORDS_FILE));
     // let engine_sentences = search::Search::from_old(search::SearchOld::load_or_new(SENTENCES_FILE));
@@ -0,0 +1,138 @@
+use std::{fs, path::{Path, PathBuf}};
+use eframe::egui::ColorImage;
+
+pub const MEDIA_DIR: &str = "media";
+// Files of removed items wait here until the removal can no longer be undone
+const TRASH_DIR: &str = "trash";
+const AUDIO_EXTENSIONS: [&str; 1] = ["wav"];
+const IMAGE_EXTENSIONS: [&str; 3] = ["png", "jpg", "jpeg"];
+
+// Recordings and pictures stored as files named after the item they belong to
+#[derive(Clone, Debug)]
+pub struct Media {
+    folder: PathBuf,
+}
+
+impl Media {
+    pub fn new(folder: &str) -> Self {
+        Self { folder: PathBuf::from(folder) }
+    }
+
+    fn find(&self, name: &str, extensions: &[&str]) -> Option<PathBuf> {
+        find_in(&self.folder, name, extensions)
+    }
+
+    pub fn audio(&self, name: &str) -> Option<PathBuf> {
+        self.find(name, &AUDIO_EXTENSIONS)
+    }
+
+    pub fn image(&self, name: &str) -> Option<PathBuf> {
+        self.find(name, &IMAGE_EXTENSIONS)
+    }
+
+    // Copies the file into the media folder, replacing the recording or picture attached before
+    pub fn attach(&self, name: &str, source: &str) -> Result<PathBuf, String> {
+        let source = Path::new(source.trim());
+        let extension = source.extension().and_then(|x| x.to_str()).map(|x| x.to_lowercase()).unwrap_or_default();
+        let extensions: &[&str] = if AUDIO_EXTENSIONS.contains(&extension.as_str()) {
+            &AUDIO_EXTENSIONS
+        } else if IMAGE_EXTENSIONS.contains(&extension.as_str()) {
+            &IMAGE_EXTENSIONS
+        } else {
+            return Err(format!("Only {} and {} files can be attached", AUDIO_EXTENSIONS.join(", "), IMAGE_EXTENSIONS.join(", ")));
+        };
+        if !source.is_file() {
+            return Err(format!("{} does not exist", source.display()));
+        }
+        fs::create_dir_all(&self.folder).map_err(|x| x.to_string())?;
+        if let Some(old) = self.find(name, extensions) {
+            fs::remove_file(old).map_err(|x| x.to_string())?;
+        }
+        let target = self.folder.join(format!("{}.{}", name, extension));
+        fs::copy(source, &target).map_err(|x| x.to_string())?;
+        Ok(target)
+    }
+
+    pub fn remove(&self, path: &Path) -> Result<(), String> {
+        fs::remove_file(path).map_err(|x| x.to_string())
+    }
+
+    fn trash_folder(&self) -> PathBuf {
+        self.folder.join(TRASH_DIR)
+    }
+
+    // Moves the recording and picture of a removed item aside, so undoing the removal can bring them back
+    pub fn trash(&self, name: &str) -> Result<(), String> {
+        move_all(&self.folder, &self.trash_folder(), name)
+    }
+
+    pub fn restore(&self, name: &str) -> Result<(), String> {
+        move_all(&self.trash_folder(), &self.folder, name)
+    }
+
+    // Deletes the trashed files of a removal that can no longer be undone
+    pub fn forget(&self, name: &str) -> Result<(), String> {
+        for path in all_in(&self.trash_folder(), name) {
+            self.remove(&path)?;
+        }
+        Ok(())
+    }
+
+    // The history doesn't outlive the app, so nothing left in the trash can be restored
+    pub fn empty_trash(&self) -> Result<(), String> {
+        let trash = self.trash_folder();
+        if trash.exists() {
+            fs::remove_dir_all(trash).map_err(|x| x.to_string())?;
+        }
+        Ok(())
+    }
+}
+
+fn find_in(folder: &Path, name: &str, extensions: &[&str]) -> Option<PathBuf> {
+    extensions.iter().map(|x| folder.join(format!("{}.{}", name, x))).find(|x| x.exists())
+}
+
+fn all_in(folder: &Path, name: &str) -> Vec<PathBuf> {
+    find_in(folder, name, &AUDIO_EXTENSIONS).into_iter().chain(find_in(folder, name, &IMAGE_EXTENSIONS)).collect()
+}
+
+fn move_all(from: &Path, to: &Path, name: &str) -> Result<(), String> {
+    for path in all_in(from, name) {
+        fs::create_dir_all(to).map_err(|x| x.to_string())?;
+        fs::rename(&path, to.join(path.file_name().unwrap())).map_err(|x| x.to_string())?;
+    }
+    Ok(())
+}
+
+pub fn load_image(path: &Path) -> Result<ColorImage, String> {
+    let image = image::open(path).map_err(|x| x.to_string())?.to_rgba8();
+    let size = [image.width() as usize, image.height() as usize];
+    Ok(ColorImage::from_rgba_unmultiplied(size, image.as_flat_samples().as_slice()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn trashed_files_can_be_restored_until_forgotten() {
+        let folder = std::env::temp_dir().join(format!("french-media-{}", std::process::id()));
+        let media = Media::new(folder.to_str().unwrap());
+        fs::create_dir_all(&folder).unwrap();
+        fs::write(folder.join("word-1.wav"), b"audio").unwrap();
+        fs::write(folder.join("word-1.png"), b"image").unwrap();
+
+        media.trash("word-1").unwrap();
+        assert!(media.audio("word-1").is_none() && media.image("word-1").is_none());
+        media.restore("word-1").unwrap();
+        assert_eq!(fs::read(media.audio("word-1").unwrap()).unwrap(), b"audio");
+        assert!(media.image("word-1").is_some());
+
+        media.trash("word-1").unwrap();
+        media.forget("word-1").unwrap();
+        media.restore("word-1").unwrap();
+        let gone = media.audio("word-1").is_none() && media.image("word-1").is_none();
+        fs::remove_dir_all(&folder).unwrap();
+        assert!(gone);
+    }
+}
@@ -0,0 +1,194 @@
+use std::collections::HashMap;
+use levenshtein::levenshtein;
+use crate::search::{Item, Language, Search, Uid};
+
+#[derive(PartialEq, Clone, Copy)]
+pub enum Resolution {
+    Ours,
+    Theirs,
+}
+
+#[derive(PartialEq)]
+pub struct Conflict {
+    pub ours: Item,
+    pub theirs: Item,
+    pub resolution: Resolution,
+}
+
+// An item deleted on one side and changed on the other, the side that deleted it has None
+#[derive(PartialEq)]
+pub struct Deletion {
+    pub ours: Option<Item>,
+    pub theirs: Option<Item>,
+    pub resolution: Resolution,
+}
+
+#[derive(PartialEq, Default)]
+pub struct Merge {
+    pub added: Vec<Item>,
+    pub updated: Vec<(Item, Item)>,
+    pub removed: Vec<Item>,
+    pub conflicts: Vec<Conflict>,
+    pub deletions: Vec<Deletion>,
+    pub remap: HashMap<Uid, Uid>,
+}
+
+impl Merge {
+    // Their item with a uid that isn't taken by ours or by any of their items added so far
+    fn with_free_uid(&self, ours: &Search, their: &Item) -> Item {
+        let mut item = their.clone();
+        let mut theirs_added = self.added.iter().chain(self.deletions.iter().filter_map(|x| x.theirs.as_ref()));
+        if ours.get_index(item.uid).is_some() || theirs_added.any(|x| x.uid == item.uid) {
+            item.uid = ours.new_uid();
+        }
+        item
+    }
+}
+
+fn key(item: &Item) -> (String, u16) {
+    (item.language_string(&Language::French).cloned().unwrap_or_default(), item.category.to_u16())
+}
+
+fn keys(search: &Search) -> HashMap<(String, u16), &Item> {
+    let mut keys = HashMap::new();
+    for item in search.iter() {
+        keys.entry(key(item)).or_insert(item);
+    }
+    keys
+}
+
+// Items are matched on their french form and category. With a base the merge is three-way, so a
+// change made on only one side is taken without asking and items deleted on one side stay deleted,
+// unless the other side changed them.
+pub fn merge(ours: &Search, theirs: &Search, base: Option<&Search>) -> Merge {
+    let mut merge = Merge::default();
+    let ours_keys = keys(ours);
+    let theirs_keys = keys(theirs);
+    let base_keys = base.map(keys);
+
+    for their in theirs.iter() {
+        let key = key(their);
+        let base_item = base_keys.as_ref().and_then(|x| x.get(&key));
+        match ours_keys.get(&key) {
+            Some(our) => {
+                merge.remap.insert(their.uid, our.uid);
+                if our.same_content(their) {
+                    continue;
+                }
+                let mut theirs = their.clone();
+                theirs.uid = our.uid;
+                match base_item {
+                    Some(base_item) if base_item.same_content(our) => merge.updated.push(((*our).clone(), theirs)),
+                    Some(base_item) if base_item.same_content(their) => {}
+                    _ => merge.conflicts.push(Conflict { ours: (*our).clone(), theirs, resolution: Resolution::Ours }),
+                }
+            }
+            None => {
+                let item = merge.with_free_uid(ours, their);
+                match base_item {
+                    // Deleted by us and left alone by them
+                    Some(base_item) if base_item.same_content(their) => continue,
+                    Some(_) => {
+                        merge.remap.insert(their.uid, item.uid);
+                        merge.deletions.push(Deletion { ours: None, theirs: Some(item), resolution: Resolution::Ours });
+                    }
+                    None => {
+                        merge.remap.insert(their.uid, item.uid);
+                        merge.added.push(item);
+                    }
+                }
+            }
+        }
+    }
+
+    if let Some(base_keys) = &base_keys {
+        for our in ours.iter() {
+            let key = key(our);
+            if theirs_keys.contains_key(&key) {
+                continue;
+            }
+            match base_keys.get(&key) {
+                Some(base_item) if base_item.same_content(our) => merge.removed.push(our.clone()),
+                Some(_) => merge.deletions.push(Deletion { ours: Some(our.clone()), theirs: None, resolution: Resolution::Ours }),
+                None => {}
+            }
+        }
+    }
+
+    merge
+}
+
+#[derive(PartialEq)]
+pub struct Duplicates {
+    pub items: Vec<Item>,
+    pub keep: usize,
+    pub swedish: usize,
+    pub english: usize,
+    pub category: usize,
+}
+
+impl Duplicates {
+    pub fn merged(&self) -> Item {
+        let mut item = self.items[self.keep].clone();
+        item.swedish = self.items[self.swedish].swedish.clone();
+        item.english = self.items[self.english].english.clone();
+        item.set_category(self.items[self.category].category.clone());
+        for other in &self.items {
+            for tag in &other.tags {
+                if !item.tags.contains(tag) {
+                    item.tags.push(tag.clone());
+                }
+            }
+            for alternative in other.swedish_translations() {
+                if item.swedish.as_ref() != Some(alternative) && !item.alternatives.contains(alternative) {
+                    item.alternatives.push(alternative.clone());
+                }
+            }
+        }
+        if item.ipa.is_none() {
+            item.ipa = self.items.iter().find_map(|x| x.ipa.clone());
+        }
+        item
+    }
+}
+
+fn near_identical(a: &str, b: &str) -> bool {
+    let a = a.trim().to_lowercase();
+    let b = b.trim().to_lowercase();
+    if a == b {
+        return true;
+    }
+    let (a_len, b_len) = (a.chars().count(), b.chars().count());
+    // Short words like "le" and "la" are legitimately one letter apart
+    a_len.min(b_len) >= 5 && a_len.abs_diff(b_len) <= 1 && levenshtein(&a, &b) <= 1
+}
+
+fn root(parents: &mut [usize], mut i: usize) -> usize {
+    while parents[i] != i {
+        parents[i] = parents[parents[i]];
+        i = parents[i];
+    }
+    i
+}
+
+pub fn find_duplicates(search: &Search) -> Vec<Duplicates> {
+    let items: Vec<&Item> = search.iter().collect();
+    let frenches: Vec<String> = items.iter().map(|x| x.language_string(&Language::French).cloned().unwrap_or_default()).collect();
+    let mut parents: Vec<usize> = (0..items.len()).collect();
+    for i in 0..items.len() {
+        for j in i + 1..items.len() {
+            if items[i].category.to_u16() == items[j].category.to_u16() && near_identical(&frenches[i], &frenches[j]) {
+                let (a, b) = (root(&mut parents, i), root(&mut parents, j));
+                parents[b] = a;
+            }
+        }
+    }
+    let mut groups: HashMap<usize, Vec<Item>> = HashMap::new();
+    for (i, item) in items.iter().enumerate() {
+        let group = root(&mut parents, i);
+        groups.entry(group).or_default().push((*item).clone());
+    }
+    let mut duplicates: Vec<Duplicates> = groups.into_values().filter(|x| x.len() > 1).map(|items| Duplicates { items, keep: 0, swedish: 0, english: 0, category: 0 }).collect();
+    duplicates.sort_by(|a, b| a.items[0].language_string(&Language::French).cmp(&b.items[0].language_string(&Language::French)));
+    duplicates
+}
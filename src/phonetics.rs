@@ -0,0 +1,208 @@
+use crate::search::Category;
+
+const VOWELS: &str = "aeiouyéèêëàâîïôûùüœ";
+// Consonants that are usually silent at the end of a word, c, r, f and l usually aren't
+const SILENT_FINALS: &str = "sxztdpg";
+const ELIDED: [&str; 10] = ["le", "la", "de", "je", "me", "te", "se", "ne", "que", "ce"];
+const LIAISON_WORDS: [&str; 12] = ["est", "sont", "très", "plus", "bien", "quand", "dont", "chez", "dans", "en", "sans", "tout"];
+
+// Graphemes tried longest first, the flag marks nasal vowels which only count when no vowel or n/m follows
+const GRAPHEMES: [(&str, &str, bool); 42] = [
+    ("ain", "ɛ̃", true),
+    ("aim", "ɛ̃", true),
+    ("ein", "ɛ̃", true),
+    ("oin", "wɛ̃", true),
+    ("ien", "jɛ̃", true),
+    ("eau", "o", false),
+    ("œu", "ø", false),
+    ("an", "ɑ̃", true),
+    ("am", "ɑ̃", true),
+    ("en", "ɑ̃", true),
+    ("em", "ɑ̃", true),
+    ("in", "ɛ̃", true),
+    ("im", "ɛ̃", true),
+    ("yn", "ɛ̃", true),
+    ("un", "œ̃", true),
+    ("um", "œ̃", true),
+    ("on", "ɔ̃", true),
+    ("om", "ɔ̃", true),
+    ("ai", "ɛ", false),
+    ("ei", "ɛ", false),
+    ("au", "o", false),
+    ("ou", "u", false),
+    ("oi", "wa", false),
+    ("eu", "ø", false),
+    ("ch", "ʃ", false),
+    ("gn", "ɲ", false),
+    ("ph", "f", false),
+    ("qu", "k", false),
+    ("th", "t", false),
+    ("ss", "s", false),
+    ("ll", "l", false),
+    ("mm", "m", false),
+    ("nn", "n", false),
+    ("tt", "t", false),
+    ("pp", "p", false),
+    ("rr", "ʁ", false),
+    ("ff", "f", false),
+    ("cc", "k", false),
+    ("ç", "s", false),
+    ("j", "ʒ", false),
+    ("r", "ʁ", false),
+    ("x", "ks", false),
+];
+
+fn is_vowel(c: Option<&char>) -> bool {
+    c.is_some_and(|c| VOWELS.contains(*c))
+}
+
+fn starts_with(chars: &[char], i: usize, string: &str) -> bool {
+    string.chars().enumerate().all(|(j, c)| chars.get(i + j) == Some(&c))
+}
+
+fn word(word: &str, verb: bool) -> String {
+    let chars: Vec<char> = word.to_lowercase().chars().filter(|c| c.is_alphabetic()).collect();
+    let mut end = chars.len();
+    // How a final e is said once the consonant after it turned out to be silent
+    let mut final_e = None;
+    let has_vowel = |end: usize| chars[..end].iter().any(|c| VOWELS.contains(*c));
+    if verb && end > 3 && chars.ends_with(&['e', 'n', 't']) && has_vowel(end - 3) {
+        // Ils parlent, the ending of the third person plural is never pronounced
+        end -= 3;
+    } else {
+        if end > 2 && matches!(chars[end - 1], 's' | 'x') {
+            end -= 1;
+            if end == 2 && chars[1] == 'e' {
+                final_e = Some("e");
+            }
+        }
+        if end > 1 && chars[end - 1] == 'e' && has_vowel(end - 1) {
+            end -= 1;
+        } else if end > 3 && chars.ends_with(&['e', 'r']) && chars.len() == end {
+            end -= 1;
+            final_e = Some("e");
+        } else {
+            while end > 1 && SILENT_FINALS.contains(chars[end - 1]) && has_vowel(end - 1) {
+                if chars[end - 2] == 'e' {
+                    final_e = Some(if chars[end - 1] == 'z' { "e" } else { "ɛ" });
+                }
+                end -= 1;
+            }
+        }
+    }
+    let mut ipa = String::new();
+    let mut i = 0;
+    'outer: while i < end {
+        let (previous, next) = (i.checked_sub(1).and_then(|x| chars.get(x)), chars.get(i + 1));
+        if i > 0 && starts_with(&chars, i, "tion") {
+            ipa.push_str("sjɔ̃");
+            i += 4;
+            continue;
+        }
+        for (grapheme, sound) in [("aill", "aj"), ("eill", "ɛj"), ("ouill", "uj"), ("ill", "ij")] {
+            let length = grapheme.chars().count();
+            if i + length <= end && starts_with(&chars, i, grapheme) {
+                ipa.push_str(sound);
+                i += length;
+                continue 'outer;
+            }
+        }
+        // Travail and soleil
+        for (grapheme, sound) in [("ail", "aj"), ("eil", "ɛj")] {
+            if i + 3 == end && starts_with(&chars, i, grapheme) {
+                ipa.push_str(sound);
+                i += 3;
+                continue 'outer;
+            }
+        }
+        for (grapheme, sound, nasal) in GRAPHEMES {
+            let length = grapheme.chars().count();
+            if i + length > end || !starts_with(&chars, i, grapheme) {
+                continue;
+            }
+            if nasal {
+                let after = chars.get(i + length);
+                if is_vowel(after) || matches!(after, Some('n' | 'm')) {
+                    continue;
+                }
+            }
+            ipa.push_str(sound);
+            i += length;
+            continue 'outer;
+        }
+        let c = chars[i];
+        let soft = matches!(next, Some('e' | 'i' | 'y' | 'é' | 'è' | 'ê'));
+        match c {
+            'c' => ipa.push(if soft { 's' } else { 'k' }),
+            'g' if starts_with(&chars, i, "gu") && matches!(chars.get(i + 2), Some('e' | 'i' | 'é' | 'è' | 'ê')) => {
+                ipa.push('g');
+                i += 1;
+            }
+            'g' => ipa.push(if soft { 'ʒ' } else { 'g' }),
+            's' => ipa.push(if is_vowel(previous) && is_vowel(next) { 'z' } else { 's' }),
+            'h' => {}
+            'y' => ipa.push(if is_vowel(previous) && is_vowel(next) { 'j' } else { 'i' }),
+            'é' => ipa.push('e'),
+            'è' | 'ê' | 'ë' => ipa.push('ɛ'),
+            'à' | 'â' => ipa.push('a'),
+            'ô' => ipa.push('o'),
+            'î' | 'ï' => ipa.push('i'),
+            'u' | 'û' | 'ù' | 'ü' => ipa.push('y'),
+            'q' => ipa.push('k'),
+            // Mangeons, the e only keeps the g soft
+            'e' if previous == Some(&'g') && matches!(next, Some('a' | 'o' | 'u')) => {}
+            'e' if i + 1 == end => ipa.push_str(final_e.unwrap_or("ə")),
+            'e' => {
+                // A consonant closing the syllable opens the e
+                let closed = !is_vowel(next) && (i + 2 >= end || !is_vowel(chars.get(i + 2)));
+                ipa.push(if closed { 'ɛ' } else { 'ə' });
+            }
+            c => ipa.push(c),
+        }
+        i += 1;
+    }
+    ipa
+}
+
+// A guess at the pronunciation from the spelling, meant to be checked by the user
+pub fn suggest(french: &str, category: &Category) -> String {
+    let verb = matches!(category, Category::Verb(..));
+    french.split(|c: char| c.is_whitespace() || c == '-').filter(|x| !x.is_empty()).map(|x| word(x, verb)).collect::<Vec<String>>().join(" ")
+}
+
+pub fn starts_with_vowel_sound(word: &str) -> bool {
+    word.to_lowercase().starts_with(|c| VOWELS.contains(c) || c == 'h')
+}
+
+pub fn elides(word: &str) -> bool {
+    ELIDED.contains(&word.to_lowercase().as_str())
+}
+
+// The sound a silent final consonant gets when it is carried over to the next word
+fn liaison_sound(word: &str) -> Option<char> {
+    match word.chars().last()? {
+        's' | 'x' | 'z' => Some('z'),
+        't' | 'd' => Some('t'),
+        'n' => Some('n'),
+        'p' => Some('p'),
+        _ => None,
+    }
+}
+
+pub fn liaison(first: &str, category: Option<&Category>, second: &str) -> Option<String> {
+    let first = first.to_lowercase();
+    // Et never links to the next word
+    if first == "et" || !starts_with_vowel_sound(second) {
+        return None;
+    }
+    let links = matches!(category, Some(Category::Article(..) | Category::Pronoun(..) | Category::Number(..) | Category::Adjective(..) | Category::Preposition(..))) || LIAISON_WORDS.contains(&first.as_str());
+    if !links {
+        return None;
+    }
+    let sound = liaison_sound(&first)?;
+    let mut hint = format!("Liaison: {}‿{}, the final consonant is pronounced /{}/", first, second, sound);
+    if second.to_lowercase().starts_with('h') {
+        hint.push_str(" unless the h is aspirated");
+    }
+    Some(hint)
+}
@@ -1,7 +1,9 @@
-use std::{fmt::Display, fs::File, io::{Read, Write}};
-use crate::search::{Adjective, Category, Item, Language, Pronoun, Search, VerbForms};
+use std::{collections::HashMap, fmt::Display, fs::File, io::{Read, Write}, path::{Path, PathBuf}, time::{Duration, Instant}};
+use crate::{conjugation::{Tense, PERSONS}, explain::{explain, Part}, grade::{Grade, Leniency}, media::Media, sentence, search::{Adjective, Category, Gender, Item, Language, LegacyUids, NounCategory, Pronoun, Query, Search, Uid, VerbForms}, utils};
 use bincode::{deserialize, serialize};
-use rand::{thread_rng, Rng, seq::SliceRandom};
+use levenshtein::levenshtein;
+use rand::{Rng, SeedableRng, seq::SliceRandom};
+use rand_chacha::ChaCha8Rng;
 use serde::{Deserialize, Serialize};
 
 enum Form {
@@ -20,116 +22,358 @@ impl Display for Form {
     }
 }
 
+#[derive(Clone, Copy, Serialize, Deserialize, Debug, PartialEq)]
+pub enum Exercise {
+    Typed,
+    MultipleChoice,
+    Cloze,
+    Matching,
+    Dictation,
+    Picture,
+    Generated,
+}
+
+pub const EXERCISES: [Exercise; 7] = [Exercise::Typed, Exercise::MultipleChoice, Exercise::Cloze, Exercise::Matching, Exercise::Dictation, Exercise::Picture, Exercise::Generated];
+
+impl Display for Exercise {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", match self {
+            Self::Typed => "Typed answer",
+            Self::MultipleChoice => "Multiple choice",
+            Self::Cloze => "Fill in the blank (sentences)",
+            Self::Matching => "Matching pairs (words)",
+            Self::Dictation => "Dictation",
+            Self::Picture => "Name the picture (words)",
+            Self::Generated => "Generated sentences (words)",
+        })
+    }
+}
+
+#[derive(Clone, Copy, Serialize, Deserialize, Debug, PartialEq)]
+pub enum Direction {
+    FrenchToSwedish,
+    SwedishToFrench,
+    Both,
+    English,
+}
+
+pub const DIRECTIONS: [Direction; 4] = [Direction::FrenchToSwedish, Direction::SwedishToFrench, Direction::Both, Direction::English];
+
+impl Display for Direction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", match self {
+            Self::FrenchToSwedish => "French to Swedish",
+            Self::SwedishToFrench => "Swedish to French",
+            Self::Both => "French and Swedish, both ways",
+            Self::English => "French and English, both ways",
+        })
+    }
+}
+
+impl Direction {
+    // The language to answer in and the language the translation is given in
+    fn languages(&self, rng: &mut impl Rng) -> (Language, Language) {
+        match self {
+            Self::FrenchToSwedish => (Language::Swedish, Language::Swedish),
+            Self::SwedishToFrench => (Language::French, Language::Swedish),
+            Self::Both => (rng.gen::<Language>(), Language::Swedish),
+            Self::English => (if rng.gen_bool(0.5) { Language::French } else { Language::English }, Language::English),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, PartialEq, Clone, Debug)]
+pub struct GroupSettings {
+    pub direction: Direction,
+    pub singular: bool,
+    pub plural: bool,
+    pub male: bool,
+    pub female: bool,
+    pub tenses: Vec<Tense>,
+    pub persons: [bool; 6],
+    // Questions per session and seconds per question
+    pub question_count: Option<u32>,
+    pub time_limit: Option<u32>,
+}
+
+impl Default for GroupSettings {
+    fn default() -> Self {
+        Self {
+            direction: Direction::Both,
+            singular: true,
+            plural: true,
+            male: true,
+            female: true,
+            tenses: vec![Tense::Present],
+            persons: [true; 6],
+            question_count: None,
+            time_limit: None,
+        }
+    }
+}
+
+impl GroupSettings {
+    // Whether a form named like "plural female" may be asked
+    fn allows(&self, name: &str) -> bool {
+        name.split(' ').all(|x| match x {
+            "singular" => self.singular,
+            "plural" => self.plural,
+            "male" => self.male,
+            "female" => self.female,
+            _ => true,
+        })
+    }
+}
+
+const CHOICES: usize = 4;
+const MATCHING_PAIRS: usize = 5;
+
+#[derive(PartialEq, Clone)]
+pub struct Pair {
+    pub french: String,
+    pub swedish: String,
+    pub index: usize,
+    pub matched: bool,
+    pub mistake: bool,
+}
+
+#[derive(PartialEq)]
+pub enum QuestionKind {
+    Typed,
+    Choice(Vec<String>),
+    // The pairs, the order of the swedish column and the selected french word
+    Matching(Vec<Pair>, Vec<usize>, Option<usize>),
+    // Whether the text has been spoken yet
+    Dictation(bool),
+    // The picture attached to the word
+    Picture(PathBuf),
+    // The words of a generated sentence and the items they were made from
+    Generated(Vec<(String, Option<Uid>)>),
+}
+
 #[derive(PartialEq)]
 pub struct Question {
     pub string: String,
     pub answer: String,
     pub language: Language,
     pub item: Item,
+    pub kind: QuestionKind,
+    // The french text the question is about, to be pronounced
+    pub french: String,
 }
 
 impl Question {
-    fn translate(french: String, swedish: String, to_language: Language, item: Item) -> Self {
+    fn answer_form(&self) -> Option<&'static str> {
+        let (required, optional) = self.item.category.forms();
+        required.into_iter().chain(optional).find(|(_, form)| **form == self.answer).map(|(name, _)| name)
+    }
+
+    // Other items with the same translation and category are accepted too, as are explicit alternative translations
+    pub fn accepted_answers(&self, search: &Search) -> Vec<String> {
+        // Synonyms don't sound the same, so only the spoken text counts
+        if matches!(self.kind, QuestionKind::Dictation(_) | QuestionKind::Generated(_)) {
+            return vec![self.answer.clone()];
+        }
+        let mut answers = vec![self.answer.clone()];
+        let translations: Vec<String> = self.item.swedish_translations().iter().map(|x| x.trim().to_lowercase()).collect();
+        let others = search.iter().filter(|x| x.uid != self.item.uid && x.category.to_u16() == self.item.category.to_u16());
+        match self.language {
+            Language::French => {
+                if let Some(name) = self.answer_form() {
+                    for other in others.filter(|x| x.swedish_translations().iter().any(|t| translations.contains(&t.trim().to_lowercase()))) {
+                        let (required, optional) = other.category.forms();
+                        answers.extend(required.into_iter().chain(optional).filter(|(x, _)| *x == name).map(|(_, form)| form.clone()));
+                    }
+                }
+            }
+            Language::Swedish => {
+                answers.extend(self.item.alternatives.iter().cloned());
+                let french = self.item.language_string(&Language::French);
+                for other in others.filter(|x| x.language_string(&Language::French) == french) {
+                    answers.extend(other.swedish_translations().into_iter().cloned());
+                }
+            }
+            Language::English => {}
+        }
+        let mut unique: Vec<String> = vec![];
+        for answer in answers {
+            if !answer.trim().is_empty() && !unique.contains(&answer) {
+                unique.push(answer);
+            }
+        }
+        unique
+    }
+
+    // Distractors are the same form of other items of the same category, preferring similar spelling
+    fn with_choices(mut self, search: &Search, rng: &mut impl Rng) -> Option<Self> {
+        let accepted = self.accepted_answers(search);
+        let name = self.answer_form();
+        let mut candidates: Vec<String> = search.iter()
+            .filter(|x| x.uid != self.item.uid && x.category.to_u16() == self.item.category.to_u16())
+            .filter_map(|other| match self.language {
+                Language::French => {
+                    let (required, optional) = other.category.forms();
+                    required.into_iter().chain(optional).find(|(x, _)| Some(*x) == name).map(|(_, form)| form.clone())
+                }
+                Language::Swedish => other.swedish.clone(),
+                Language::English => other.english.clone(),
+            })
+            .filter(|x| !x.trim().is_empty() && !accepted.contains(x))
+            .collect();
+        candidates.sort_by_key(|x| levenshtein(x, &self.answer));
+        let mut choices: Vec<String> = vec![];
+        for candidate in candidates {
+            if choices.len() == CHOICES - 1 {
+                break;
+            }
+            if !choices.contains(&candidate) {
+                choices.push(candidate);
+            }
+        }
+        if choices.is_empty() {
+            return None;
+        }
+        choices.push(self.answer.clone());
+        choices.shuffle(rng);
+        self.kind = QuestionKind::Choice(choices);
+        Some(self)
+    }
+
+    fn translate(french: String, translation: String, to_language: Language, item: Item) -> Self {
         match to_language {
-            Language::French => Self { string: format!("What is '{}' in french?", swedish), answer: french, language: to_language, item },
-            Language::Swedish => Self { string: format!("What is '{}' in swedish?", french), answer: swedish, language: to_language, item },
-            Language::English => unreachable!(),
+            Language::French => Self { string: format!("What is '{}' in french?", translation), answer: french.clone(), language: to_language, item, kind: QuestionKind::Typed, french },
+            Language::Swedish => Self { string: format!("What is '{}' in swedish?", french), answer: translation, language: to_language, item, kind: QuestionKind::Typed, french },
+            Language::English => Self { string: format!("What is '{}' in english?", french), answer: translation, language: to_language, item, kind: QuestionKind::Typed, french },
         }
     }
 
-    fn translate_adjective(french: String, swedish: String, to_language: Language, item: Item) -> Self {
+    fn translate_adjective(french: String, translation: String, to_language: Language, item: Item) -> Self {
         match to_language {
-            Language::French => Self { string: format!("What is '{}' in french (masculine)?", swedish), answer: french, language: to_language, item },
-            Language::Swedish => Self { string: format!("What is '{}' in swedish?", french), answer: swedish, language: to_language, item },
-            Language::English => unreachable!(),
+            Language::French => Self { string: format!("What is '{}' in french (masculine)?", translation), answer: french.clone(), language: to_language, item, kind: QuestionKind::Typed, french },
+            Language::Swedish => Self { string: format!("What is '{}' in swedish?", french), answer: translation, language: to_language, item, kind: QuestionKind::Typed, french },
+            Language::English => Self { string: format!("What is '{}' in english?", french), answer: translation, language: to_language, item, kind: QuestionKind::Typed, french },
         }
     }
 
-    fn translate_verb(french: String, swedish: String, form: &str, to_language: Language, item: Item) -> Self {
+    fn translate_verb(french: String, translation: String, form: &str, to_language: Language, item: Item) -> Self {
         match to_language {
-            Language::French => Self { string: format!("What is the {} form of '{}' in french?", form, swedish), answer: french, language: to_language, item },
-            Language::Swedish => Self { string: format!("What is '{}' ({}) in swedish?", french, form), answer: swedish, language: to_language, item },
-            Language::English => unreachable!(),
+            Language::French => Self { string: format!("What is the {} form of '{}' in french?", form, translation), answer: french.clone(), language: to_language, item, kind: QuestionKind::Typed, french },
+            Language::Swedish => Self { string: format!("What is '{}' ({}) in swedish?", french, form), answer: translation, language: to_language, item, kind: QuestionKind::Typed, french },
+            Language::English => Self { string: format!("What is '{}' ({}) in english?", french, form), answer: translation, language: to_language, item, kind: QuestionKind::Typed, french },
         }
     }
 
-    fn translate_form(french: String, swedish: String, form: Form, to_language: Language, item: Item) -> Self {
+    fn translate_form(french: String, translation: String, form: Form, to_language: Language, item: Item) -> Self {
         match to_language {
-            Language::French => Self { string: format!("What is the {} form of '{}' in french?", form, swedish), answer: french, language: to_language, item },
-            Language::Swedish => Self { string: format!("What is '{}' ({}) in swedish?", french, form), answer: swedish, language: to_language, item },
-            Language::English => unreachable!(),
+            Language::French => Self { string: format!("What is the {} form of '{}' in french?", form, translation), answer: french.clone(), language: to_language, item, kind: QuestionKind::Typed, french },
+            Language::Swedish => Self { string: format!("What is '{}' ({}) in swedish?", french, form), answer: translation, language: to_language, item, kind: QuestionKind::Typed, french },
+            Language::English => Self { string: format!("What is '{}' ({}) in english?", french, form), answer: translation, language: to_language, item, kind: QuestionKind::Typed, french },
         }
     }
 
     fn translate_number(french: String, num: String, to_language: Language, item: Item) -> Self {
         match to_language {
-            Language::French => Self { string: format!("What is '{}' in french?", num), answer: french, language: to_language, item },
-            Language::Swedish => Self { string: format!("What number is '{}'?", french), answer: num, language: to_language, item },
-            Language::English => unreachable!(),
+            Language::French => Self { string: format!("What is '{}' in french?", num), answer: french.clone(), language: to_language, item, kind: QuestionKind::Typed, french },
+            Language::Swedish | Language::English => Self { string: format!("What number is '{}'?", french), answer: num, language: to_language, item, kind: QuestionKind::Typed, french },
         }
     }
 
-    fn translate_plural(french: String, swedish: String, to_language: Language, item: Item) -> Self {
+    fn translate_plural(french: String, translation: String, to_language: Language, item: Item) -> Self {
         match to_language {
-            Language::French => Self { string: format!("What is '{}' in french plural?", swedish), answer: french, language: to_language, item },
-            Language::Swedish => Self { string: format!("What is '{}' (plural) in swedish?", french), answer: swedish, language: to_language, item },
-            Language::English => unreachable!(),
+            Language::French => Self { string: format!("What is '{}' in french plural?", translation), answer: french.clone(), language: to_language, item, kind: QuestionKind::Typed, french },
+            Language::Swedish => Self { string: format!("What is '{}' (plural) in swedish?", french), answer: translation, language: to_language, item, kind: QuestionKind::Typed, french },
+            Language::English => Self { string: format!("What is '{}' (plural) in english?", french), answer: translation, language: to_language, item, kind: QuestionKind::Typed, french },
         }
     }
 }
 
-fn generate_practice_question(item: Item) -> Question {
-    let to_language = thread_rng().gen::<Language>();
-    match item.category {
+fn generate_practice_question(item: Item, settings: &GroupSettings, rng: &mut impl Rng) -> Option<Question> {
+    let (to_language, translation_language) = settings.direction.languages(rng);
+    let translation = item.language_string(&translation_language).cloned().filter(|x| !x.trim().is_empty())?;
+    let question = match item.category {
         Category::Other(ref s) |
         Category::Adverb(ref s) |
         Category::Conjunction(ref s) |
         Category::Interjection(ref s) |
         Category::Preposition(ref s) => {
-            Question::translate(s.clone(), item.swedish.clone().unwrap(), to_language, item)
+            Question::translate(s.clone(), translation, to_language, item)
         }
         Category::Adjective(ref adjective) => {
-            match adjective {
-                Adjective::Descriptive(s, ..) |
-                Adjective::Indefinite(s, ..) |
-                Adjective::ExclamativeInterrogative(s, ..) |
-                Adjective::Past(s, ..) |
-                Adjective::Present(s, ..) |
-                Adjective::Relative(s, ..) |
-                Adjective::Demonstrative(s, ..) |
-                Adjective::Negative(s, _) |
-                Adjective::Possessive(s, ..) => Question::translate_adjective(s.clone(), item.swedish.clone().unwrap(), to_language, item),
+            // The first form is the masculine singular, the other forms are only asked when the settings allow them
+            let (forms, _) = item.category.forms();
+            let first = forms[0].0;
+            let allowed: Vec<(&str, String)> = forms.into_iter().filter(|(name, form)| settings.allows(name) && !form.trim().is_empty()).map(|(name, form)| (name, form.clone())).collect();
+            match allowed.choose(rng) {
+                Some((name, form)) if *name != first => Question::translate_verb(form.clone(), translation, name, to_language, item),
+                _ => match adjective {
+                    Adjective::Descriptive(s, ..) |
+                    Adjective::Indefinite(s, ..) |
+                    Adjective::ExclamativeInterrogative(s, ..) |
+                    Adjective::Past(s, ..) |
+                    Adjective::Present(s, ..) |
+                    Adjective::Relative(s, ..) |
+                    Adjective::Demonstrative(s, ..) |
+                    Adjective::Negative(s, _) |
+                    Adjective::Possessive(s, ..) => Question::translate_adjective(s.clone(), translation, to_language, item),
+                }
             }
         }
         Category::Noun(ref noun) => {
-            match thread_rng().gen_range(0..=1) {
-                0 => Question::translate(noun.singular.clone(), item.swedish.clone().unwrap(), to_language, item),
-                _ => Question::translate_plural(noun.plural.clone(), item.swedish.clone().unwrap(), to_language, item),
+            let plural = match (settings.singular, settings.plural) {
+                (true, false) => false,
+                (false, true) => true,
+                _ => rng.gen_bool(0.5),
+            };
+            if plural {
+                Question::translate_plural(noun.plural.clone(), translation, to_language, item)
+            } else {
+                Question::translate(noun.singular.clone(), translation, to_language, item)
             }
-            
         }
         Category::Verb(_, ref forms) => {
             let (VerbForms::Regular(je, tu, il, nous, vous, ils, pc, imp_je, imp_tu, imp_il, imp_nous, imp_vous, imp_ils) | VerbForms::Irregular(je, tu, il, nous, vous, ils, pc, imp_je, imp_tu, imp_il, imp_nous, imp_vous, imp_ils)) = forms.clone();
-            match thread_rng().gen_range(0..=5) {
-                0 => Question::translate_verb(je, item.swedish.clone().unwrap(), "je", to_language, item),
-                1 => Question::translate_verb(tu, item.swedish.clone().unwrap(), "tu", to_language, item),
-                2 => Question::translate_verb(il, item.swedish.clone().unwrap(), "il/elle/on", to_language, item),
-                3 => Question::translate_verb(nous, item.swedish.clone().unwrap(), "nous", to_language, item),
-                4 => Question::translate_verb(vous, item.swedish.clone().unwrap(), "vous", to_language, item),
-                _ => Question::translate_verb(ils, item.swedish.clone().unwrap(), "ils/elles", to_language, item),
+            let present = [je, tu, il, nous, vous, ils];
+            let mut candidates: Vec<(String, String)> = vec![];
+            for tense in &settings.tenses {
+                match tense {
+                    Tense::Present => {
+                        candidates.extend((0..6).filter(|i| settings.persons[*i]).map(|i| (present[i].clone(), PERSONS[i].to_string())));
+                    }
+                    Tense::Imparfait => {
+                        let imparfait = [&imp_je, &imp_tu, &imp_il, &imp_nous, &imp_vous, &imp_ils];
+                        candidates.extend((0..6).filter(|i| settings.persons[*i]).map(|i| (imparfait[i].clone(), format!("imparfait {}", PERSONS[i]))));
+                    }
+                    Tense::PasseCompose => candidates.push((pc.clone(), "passé composé".to_string())),
+                }
             }
+            candidates.retain(|(form, _)| !form.trim().is_empty());
+            let (french, form) = candidates.choose(rng).cloned().unwrap_or_else(|| {
+                let i = rng.gen_range(0..6);
+                (present[i].clone(), PERSONS[i].to_string())
+            });
+            Question::translate_verb(french, translation, &form, to_language, item)
         }
         Category::Article(ref m, ref f, ref p, _) => {
-            match thread_rng().gen_range(0..=2) {
-                0 => Question::translate_form(m.clone(), item.swedish.clone().unwrap(), Form::Male, to_language, item),
-                1 => Question::translate_form(f.clone(), item.swedish.clone().unwrap(), Form::Female, to_language, item),
-                _ => Question::translate_form(p.clone(), item.swedish.clone().unwrap(), Form::Plural, to_language, item),
+            let mut forms = vec![];
+            if settings.male {
+                forms.push((m.clone(), Form::Male));
+            }
+            if settings.female {
+                forms.push((f.clone(), Form::Female));
+            }
+            if settings.plural {
+                forms.push((p.clone(), Form::Plural));
+            }
+            if forms.is_empty() {
+                forms = vec![(m.clone(), Form::Male), (f.clone(), Form::Female), (p.clone(), Form::Plural)];
             }
+            let (french, form) = forms.swap_remove(rng.gen_range(0..forms.len()));
+            Question::translate_form(french, translation, form, to_language, item)
         }
         Category::Number(ref c, _, ref o, _, _, _, _, _) => {
-            match thread_rng().gen_range(0..=1) {
-                0 => Question::translate_number(c.clone(), item.swedish.clone().unwrap(), to_language, item),
-                _ => Question::translate_number(o.clone(), item.swedish.clone().unwrap(), to_language, item),
+            match rng.gen_range(0..=1) {
+                0 => Question::translate_number(c.clone(), translation, to_language, item),
+                _ => Question::translate_number(o.clone(), translation, to_language, item),
             }
         }
         Category::Pronoun(ref p) => {
@@ -144,53 +388,240 @@ fn generate_practice_question(item: Item) -> Question {
                 Pronoun::Possessive(s, _, _, _) |
                 Pronoun::Demonstrative(s, _, _, _) |
                 Pronoun::Relative(s, _) |
-                Pronoun::Indefinite(s, _) => Question::translate_adjective(s.clone(), item.swedish.clone().unwrap(), to_language, item),
+                Pronoun::Indefinite(s, _) => Question::translate_adjective(s.clone(), translation, to_language, item),
             }
         }
+    };
+    Some(question)
+}
+
+fn find_word(sentence: &str, word: &str) -> Option<usize> {
+    sentence.match_indices(word).map(|(i, _)| i).find(|i| {
+        let before = sentence[..*i].chars().next_back();
+        let after = sentence[i + word.len()..].chars().next();
+        !before.is_some_and(|c| c.is_alphabetic()) && !after.is_some_and(|c| c.is_alphabetic())
+    })
+}
+
+// Blanks out one of the words in the sentence that could be found in the word list
+fn generate_cloze(item: Item, words: &Search, rng: &mut impl Rng) -> Option<Question> {
+    let Category::Other(sentence) = &item.category else {
+        return None;
+    };
+    let parts: Vec<Part> = explain(sentence, words).into_iter().filter(|x| x.sure && x.string.chars().count() > 2).collect();
+    let part = parts.choose(rng)?;
+    let start = find_word(sentence, &part.string)?;
+    let blanked = format!("{}___{}", &sentence[..start], &sentence[start + part.string.len()..]);
+    let string = match &item.swedish {
+        Some(swedish) => format!("Fill in the blank: {} ({})", blanked, swedish),
+        None => format!("Fill in the blank: {}", blanked),
+    };
+    Some(Question { string, answer: part.string.clone(), language: Language::French, item, kind: QuestionKind::Typed, french: part.string.clone() })
+}
+
+fn generate_dictation(item: Item) -> Option<Question> {
+    let french = item.language_string(&Language::French).cloned().filter(|x| !x.trim().is_empty())?;
+    Some(Question { string: "Write what you hear in french".to_string(), answer: french.clone(), language: Language::French, item, kind: QuestionKind::Dictation(false), french })
+}
+
+fn generate_picture(item: Item, media: &Media) -> Option<Question> {
+    let picture = media.image(&QuestionTemplate::Word(item.uid).media_name())?;
+    let french = item.language_string(&Language::French).cloned().filter(|x| !x.trim().is_empty())?;
+    Some(Question { string: "What is this in french?".to_string(), answer: french.clone(), language: Language::French, item, kind: QuestionKind::Picture(picture), french })
+}
+
+// A new sentence built around the word, to be written in french from its translation skeleton
+fn generate_sentence(item: Item, vocabulary: &Search, settings: &GroupSettings, rng: &mut impl Rng) -> Option<Question> {
+    let (subject, verb) = match item.category {
+        Category::Verb(..) => (None, Some(item.clone())),
+        Category::Noun(..) => (Some(item.clone()), None),
+        _ => return None,
+    };
+    let tense = settings.tenses.choose(rng).copied();
+    let sentence = sentence::generate(vocabulary, subject, verb, tense, rng)?;
+    let (_, translation) = settings.direction.languages(rng);
+    let french = sentence.french();
+    let words = sentence.words.iter().map(|x| (x.french.clone(), x.item.as_ref().map(|x| x.uid))).collect();
+    Some(Question { string: format!("Write in french: {}", sentence.skeleton(&translation)), answer: french.clone(), language: Language::French, item, kind: QuestionKind::Generated(words), french })
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq, Eq, Hash)]
+pub enum QuestionTemplate {
+    Word(Uid),
+    Sentence(Uid),
+}
+
+impl QuestionTemplate {
+    // Words and sentences have separate uids, so files attached to them are named by both
+    pub fn media_name(&self) -> String {
+        match self {
+            Self::Word(uid) => format!("word-{}", uid),
+            Self::Sentence(uid) => format!("sentence-{}", uid),
+        }
+    }
+
+    pub fn exists(&self, search_words: &Search, search_sentences: &Search) -> bool {
+        match self {
+            Self::Word(uid) => search_words.get_index(*uid).is_some(),
+            Self::Sentence(uid) => search_sentences.get_index(*uid).is_some(),
+        }
     }
 }
 
 #[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
-pub enum QuestionTemplate {
+pub enum QuestionTemplateOld {
     Word(u32),
     Sentence(u32),
 }
 
-// #[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
-// pub enum QuestionTemplateOld {
-//     Word(usize),
-//     Sentence(usize),
-// }
+impl QuestionTemplate {
+    // Questions on items that weren't in the migrated files are dropped
+    fn from_old(old: QuestionTemplateOld, words: &LegacyUids, sentences: &LegacyUids) -> Option<Self> {
+        match old {
+            QuestionTemplateOld::Word(uid) => words.get(&uid).map(|x| Self::Word(*x)),
+            QuestionTemplateOld::Sentence(uid) => sentences.get(&uid).map(|x| Self::Sentence(*x)),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, PartialEq, Clone, Debug, Default)]
+pub struct Accuracy {
+    pub correct: u32,
+    pub total: u32,
+    pub mistakes: HashMap<Grade, u32>,
+}
+
+impl Accuracy {
+    pub fn percent(&self) -> u32 {
+        (self.correct * 100).checked_div(self.total).unwrap_or(0)
+    }
+}
+
+pub const SECONDS_PER_DAY: u64 = 60 * 60 * 24;
+// How many of the most missed items a session summary keeps
+const HARDEST_ITEMS: usize = 3;
+pub const SPRINT_SECONDS: u64 = 120;
+
+#[derive(Serialize, Deserialize, PartialEq, Clone, Debug)]
+pub struct Session {
+    pub group: String,
+    pub started: u64,
+    pub seconds: u64,
+    pub correct: u32,
+    pub total: u32,
+    pub sprint: bool,
+    // The most missed items with how many times they were missed
+    pub hardest: Vec<(QuestionTemplate, u32)>,
+    pub replay: Replay,
+}
+
+// What a session was started with. Starting again from it asks the same questions in the same order,
+// as long as they are answered the same way and the items themselves haven't changed.
+#[derive(Serialize, Deserialize, PartialEq, Clone, Debug)]
+pub struct Replay {
+    pub seed: u64,
+    pub questions: Vec<QuestionTemplate>,
+    pub exercises: Vec<Exercise>,
+    pub settings: GroupSettings,
+}
+
+impl Session {
+    pub fn percent(&self) -> u32 {
+        (self.correct * 100).checked_div(self.total).unwrap_or(0)
+    }
+}
+
+#[derive(Serialize, Deserialize, PartialEq, Clone, Debug, Default)]
+pub struct GroupQuery {
+    pub categories: u16,
+    pub gender: Option<Gender>,
+    pub noun_category: Option<NounCategory>,
+    pub tags: Vec<String>,
+    pub added_within_days: Option<u32>,
+    pub accuracy_below: Option<u32>,
+}
+
+impl GroupQuery {
+    fn matches(&self, item: &Item, accuracy: Option<&Accuracy>, now: u64) -> bool {
+        if self.categories != 0 && item.category.to_u16() & self.categories == 0 {
+            return false;
+        }
+        if self.gender.is_some() || self.noun_category.is_some() {
+            match &item.category {
+                Category::Noun(noun) => {
+                    if self.gender.as_ref().is_some_and(|gender| *gender != noun.gender) || self.noun_category.as_ref().is_some_and(|category| *category != noun.category) {
+                        return false;
+                    }
+                }
+                _ => return false,
+            }
+        }
+        if let Some(days) = self.added_within_days {
+            if item.added + days as u64 * SECONDS_PER_DAY < now {
+                return false;
+            }
+        }
+        if let Some(percent) = self.accuracy_below {
+            // Words that have never been practiced count as 0% so they are picked up too
+            if accuracy.map_or(0, |x| x.percent()) >= percent {
+                return false;
+            }
+        }
+        true
+    }
+}
 
 #[derive(Serialize, Deserialize, PartialEq, Clone)]
 pub struct PracticeGroup {
     pub name: String,
     pub questions: Vec<QuestionTemplate>,
+    pub query: Option<GroupQuery>,
+    pub exercises: Vec<Exercise>,
+    pub settings: GroupSettings,
 }
 
-// #[derive(Serialize, Deserialize, PartialEq, Clone)]
-// pub struct PracticeGroupOld {
-//     pub name: String,
-//     pub questions: Vec<QuestionTemplateOld>,
-// }
+#[derive(Serialize, Deserialize, PartialEq, Clone)]
+pub struct PracticeGroupOld {
+    pub name: String,
+    pub questions: Vec<QuestionTemplateOld>,
+}
 
 impl PracticeGroup {
     pub fn new(name: String) -> Self {
-        Self { name, questions: vec![] }
+        Self { name, questions: vec![], query: None, exercises: vec![Exercise::Typed], settings: GroupSettings::default() }
     }
 
     pub fn new_with_questions(name: String, questions: Vec<QuestionTemplate>) -> Self {
-        Self { name, questions }
+        Self { name, questions, query: None, exercises: vec![Exercise::Typed], settings: GroupSettings::default() }
+    }
+
+    pub fn new_with_query(name: String, query: GroupQuery) -> Self {
+        Self { name, questions: vec![], query: Some(query), exercises: vec![Exercise::Typed], settings: GroupSettings::default() }
     }
 
-    // pub fn from_old(old: PracticeGroupOld) -> Self {
-    //     Self { name: old.name, questions: vec![] }
-    // }
+    fn from_old(old: PracticeGroupOld, words: &LegacyUids, sentences: &LegacyUids) -> Self {
+        Self::new_with_questions(old.name, old.questions.into_iter().filter_map(|x| QuestionTemplate::from_old(x, words, sentences)).collect())
+    }
 }
 
 #[derive(Debug)]
 pub struct Practice {
+    media: Media,
+    // Every random choice of a session comes from this, so a session can be replayed from its seed.
+    // ChaCha is used because its output is the same across rand releases.
+    rng: ChaCha8Rng,
+    seed: u64,
     templates: Vec<QuestionTemplate>,
+    exercises: Vec<Exercise>,
+    settings: GroupSettings,
+    asked: u32,
+    started: Instant,
+    group: String,
+    session_started: (u64, Instant),
+    sprint: Option<Duration>,
+    correct: u32,
+    total: u32,
+    mistakes: HashMap<usize, u32>,
     questions: Vec<usize>,
     question: usize,
     question_index: usize,
@@ -200,38 +631,129 @@ pub struct Practice {
 }
 
 impl Practice {
-    pub fn get_question(&mut self, words: &Search, sentences: &Search) -> Question {
-        let mut rng = thread_rng();
-        self.question += 1;
-        if self.question == self.questions.len() {
-            self.question = 0;
-            self.questions.shuffle(&mut rng);
-        }
-        if self.to_repeat.len() != 0 && rng.gen_bool(0.3) {
-            self.question -= 1;
-            let to_repeat_index = rng.gen_range(0..self.to_repeat.len());
-            self.question_index = self.to_repeat.swap_remove(to_repeat_index);
-        } else {
-            self.question_index = self.questions[self.question];
+    pub fn get_question(&mut self, words: &Search, sentences: &Search) -> Option<Question> {
+        if self.session_over() {
+            return None;
         }
-        self.gen_question(words, sentences)
+        for _ in 0..self.templates.len() * 2 + 1 {
+            self.question += 1;
+            if self.question >= self.questions.len() {
+                self.question = 0;
+                self.questions.shuffle(&mut self.rng);
+            }
+            if self.to_repeat.len() != 0 && self.rng.gen_bool(0.3) {
+                self.question = self.question.saturating_sub(1);
+                let to_repeat_index = self.rng.gen_range(0..self.to_repeat.len());
+                self.question_index = self.to_repeat.swap_remove(to_repeat_index);
+            } else if let Some(index) = self.questions.get(self.question) {
+                self.question_index = *index;
+            } else {
+                return None;
+            }
+            match self.gen_question(words, sentences) {
+                Some(question) => {
+                    self.asked += 1;
+                    self.started = Instant::now();
+                    return Some(question);
+                }
+                // The item was deleted after the practice started or has no translation, so count it as known and skip it
+                None => self.answers[self.question_index] = true,
+            }
+        }
+        None
     }
 
-    fn gen_question(&self, words: &Search, sentences: &Search) -> Question {
-        match self.templates[self.question_index] {
-            QuestionTemplate::Sentence(uid) => {
-                generate_practice_question(sentences.get_item(uid).unwrap())
+    fn gen_question(&mut self, words: &Search, sentences: &Search) -> Option<Question> {
+        let (item, search, sentence) = match self.templates[self.question_index] {
+            QuestionTemplate::Sentence(uid) => (sentences.get_item(uid)?, sentences, true),
+            QuestionTemplate::Word(uid) => (words.get_item(uid)?, words, false),
+        };
+        let mut exercises: Vec<Exercise> = self.exercises.iter().copied().filter(|x| match x {
+            Exercise::Cloze => sentence,
+            Exercise::Matching | Exercise::Picture | Exercise::Generated => !sentence,
+            _ => true,
+        }).collect();
+        exercises.shuffle(&mut self.rng);
+        // Exercises that can't be made for this item fall through to the next one
+        for exercise in exercises {
+            let question = match exercise {
+                Exercise::Typed => generate_practice_question(item.clone(), &self.settings, &mut self.rng),
+                Exercise::MultipleChoice => generate_practice_question(item.clone(), &self.settings, &mut self.rng).and_then(|x| x.with_choices(search, &mut self.rng)),
+                Exercise::Cloze => generate_cloze(item.clone(), words, &mut self.rng),
+                Exercise::Matching => self.gen_matching(words),
+                Exercise::Dictation => generate_dictation(item.clone()),
+                Exercise::Picture => generate_picture(item.clone(), &self.media),
+                Exercise::Generated => {
+                    let vocabulary = self.vocabulary(words);
+                    generate_sentence(item.clone(), &vocabulary, &self.settings, &mut self.rng)
+                }
+            };
+            if question.is_some() {
+                return question;
             }
-            QuestionTemplate::Word(uid) => {
-                generate_practice_question(words.get_item(uid).unwrap())
+        }
+        generate_practice_question(item, &self.settings, &mut self.rng)
+    }
+
+    // The words of the group, along with the articles and subject pronouns every sentence needs
+    fn vocabulary(&self, words: &Search) -> Search {
+        let mut uids: Vec<Uid> = self.templates.iter().filter_map(|x| match x {
+            QuestionTemplate::Word(uid) => Some(*uid),
+            QuestionTemplate::Sentence(_) => None,
+        }).collect();
+        uids.extend(words.iter().filter(|x| matches!(x.category, Category::Article(..) | Category::Pronoun(Pronoun::Personal(..)))).map(|x| x.uid));
+        words.subset(&uids)
+    }
+
+    fn gen_matching(&mut self, words: &Search) -> Option<Question> {
+        let mut others: Vec<usize> = (0..self.templates.len()).filter(|x| *x != self.question_index).collect();
+        others.shuffle(&mut self.rng);
+        let mut pairs: Vec<Pair> = vec![];
+        for index in std::iter::once(self.question_index).chain(others) {
+            if pairs.len() == MATCHING_PAIRS {
+                break;
             }
+            let QuestionTemplate::Word(uid) = self.templates[index] else {
+                continue;
+            };
+            let Some(item) = words.get_item(uid) else {
+                continue;
+            };
+            let (Some(french), Some(swedish)) = (item.language_string(&Language::French).cloned(), item.swedish.clone()) else {
+                continue;
+            };
+            // Two pairs sharing a word could be matched either way
+            if !pairs.iter().any(|x| x.french == french || x.swedish == swedish) {
+                pairs.push(Pair { french, swedish, index, matched: false, mistake: false });
+            }
+        }
+        if pairs.len() < 3 || pairs[0].index != self.question_index {
+            return None;
         }
+        pairs.shuffle(&mut self.rng);
+        let mut order: Vec<usize> = (0..pairs.len()).collect();
+        order.shuffle(&mut self.rng);
+        let QuestionTemplate::Word(uid) = self.templates[self.question_index] else {
+            return None;
+        };
+        let item = words.get_item(uid)?;
+        Some(Question { string: "Match the french words with their swedish translations".to_string(), answer: "".to_string(), language: Language::Swedish, item, kind: QuestionKind::Matching(pairs, order, None), french: "".to_string() })
     }
 
     pub fn answer(&mut self, answer: bool) -> bool {
-        self.answers[self.question_index] = answer;
+        self.answer_index(self.question_index, answer)
+    }
+
+    pub fn answer_index(&mut self, index: usize, answer: bool) -> bool {
+        self.answers[index] = answer;
+        self.total += 1;
+        if answer {
+            self.correct += 1;
+        } else {
+            *self.mistakes.entry(index).or_default() += 1;
+        }
         if !answer {
-            self.to_repeat.push(self.question_index);
+            self.to_repeat.push(index);
             false
         } else if !self.continuing && self.answers.iter().all(|x| *x) {
             true
@@ -240,56 +762,137 @@ impl Practice {
         }
     }
 
+    pub fn session_over(&self) -> bool {
+        self.settings.question_count.is_some_and(|x| self.asked >= x) || self.sprint_left().is_some_and(|x| x.is_zero())
+    }
+
+    pub fn start_sprint(&mut self, duration: Duration) {
+        self.sprint = Some(duration);
+        self.session_started = (utils::now(), Instant::now());
+    }
+
+    pub fn sprint_left(&self) -> Option<Duration> {
+        self.sprint.map(|x| x.saturating_sub(self.session_started.1.elapsed()))
+    }
+
+    // Ends the session, returning its summary if anything was answered
+    pub fn finish(&mut self) -> Option<Session> {
+        let total = std::mem::take(&mut self.total);
+        let correct = std::mem::take(&mut self.correct);
+        let mut mistakes: Vec<(usize, u32)> = std::mem::take(&mut self.mistakes).into_iter().collect();
+        let sprint = self.sprint.take().is_some();
+        if total == 0 {
+            return None;
+        }
+        mistakes.sort_by_key(|(_, count)| std::cmp::Reverse(*count));
+        let hardest = mistakes.into_iter().take(HARDEST_ITEMS).map(|(index, count)| (self.templates[index].clone(), count)).collect();
+        let (started, instant) = self.session_started;
+        Some(Session { group: self.group.clone(), started, seconds: instant.elapsed().as_secs(), correct, total, sprint, hardest, replay: Replay { seed: self.seed, questions: self.templates.clone(), exercises: self.exercises.clone(), settings: self.settings.clone() } })
+    }
+
+    pub fn asked(&self) -> u32 {
+        self.asked
+    }
+
+    pub fn time_left(&self) -> Option<Duration> {
+        self.settings.time_limit.map(|x| Duration::from_secs(x as u64).saturating_sub(self.started.elapsed()))
+    }
+
     pub fn continue_practice(&mut self) {
         self.continuing = true;
     }
 
-    pub fn new() -> Self {
-        Self { templates: vec![], questions: vec![], question: 0, question_index: 0, to_repeat: vec![], answers: vec![], continuing: false }
+    pub fn new(media: Media) -> Self {
+        Self { media, rng: ChaCha8Rng::seed_from_u64(0), seed: 0, templates: vec![], exercises: vec![], settings: GroupSettings::default(), asked: 0, started: Instant::now(), group: "".to_string(), session_started: (0, Instant::now()), sprint: None, correct: 0, total: 0, mistakes: HashMap::new(), questions: vec![], question: 0, question_index: 0, to_repeat: vec![], answers: vec![], continuing: false }
     }
 
-    pub fn init(&mut self, group: &PracticeGroup) {
-        self.templates = group.questions.clone();
-        self.questions = (0..group.questions.len()).collect();
-        self.questions.shuffle(&mut thread_rng());
+    pub fn current_template(&self) -> &QuestionTemplate {
+        &self.templates[self.question_index]
+    }
+
+    pub fn template(&self, index: usize) -> &QuestionTemplate {
+        &self.templates[index]
+    }
+
+    pub fn init(&mut self, group: String, templates: Vec<QuestionTemplate>, exercises: Vec<Exercise>, settings: GroupSettings, seed: u64) {
+        self.seed = seed;
+        self.rng = ChaCha8Rng::seed_from_u64(self.seed);
+        self.questions = (0..templates.len()).collect();
+        self.questions.shuffle(&mut self.rng);
         self.question = 0;
         self.question_index = 0;
         self.to_repeat = vec![];
-        self.answers = vec![false; group.questions.len()];
+        self.answers = vec![false; templates.len()];
         self.continuing = false;
+        self.templates = templates;
+        self.exercises = exercises;
+        self.settings = settings;
+        self.asked = 0;
+        self.group = group;
+        self.session_started = (utils::now(), Instant::now());
+        self.sprint = None;
+        self.correct = 0;
+        self.total = 0;
+        self.mistakes.clear();
     }
 }
 
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, PartialEq)]
 pub struct PracticeGroupCollection {
     pub groups: Vec<PracticeGroup>,
+    pub accuracy: HashMap<QuestionTemplate, Accuracy>,
+    pub leniency: Leniency,
+    pub gender_accuracy: HashMap<Uid, Accuracy>,
+    pub best_gender_streak: u32,
+    pub sessions: Vec<Session>,
+    // Questions to answer per day, 0 for no goal
+    pub daily_goal: u32,
+    // Days in a row the goal was met and the last of those days
+    pub streak: u32,
+    pub streak_day: u64,
 }
 
-// #[derive(Serialize, Deserialize)]
-// pub struct PracticeGroupCollectionOld {
-//     pub groups: Vec<PracticeGroupOld>,
-// }
+#[derive(Serialize, Deserialize)]
+pub struct PracticeGroupCollectionOld {
+    pub groups: Vec<PracticeGroupOld>,
+}
 
 impl PracticeGroupCollection {
     pub fn save(&self, file: &str) {
-        let serialized_data = serialize(self).unwrap();
+        let serialized_data = utils::with_header(serialize(self).unwrap());
         let mut file = File::create(file).unwrap();
         file.write_all(&serialized_data).unwrap();
     }
 
-    pub fn load_or_new(file: &str) -> Self {
-        match File::open(file) {
-            Ok(mut file) => {
-                let mut serialized_data = Vec::new();
-                file.read_to_end(&mut serialized_data).unwrap();
-                let data: Self = deserialize(&serialized_data).unwrap();
-                data
-            }
-            Err(_) => {
-                Self {
-                    groups: vec![],
-                }
-            }
+    fn new() -> Self {
+        Self {
+            groups: vec![],
+            accuracy: HashMap::new(),
+            leniency: Leniency::default(),
+            gender_accuracy: HashMap::new(),
+            best_gender_streak: 0,
+            sessions: vec![],
+            daily_goal: 0,
+            streak: 0,
+            streak_day: 0,
+        }
+    }
+
+    pub fn load_or_new(file: &str, words: &LegacyUids, sentences: &LegacyUids) -> Result<Self, String> {
+        if !Path::new(file).exists() {
+            return Ok(Self::new());
+        }
+        Self::load(file, words, sentences)
+    }
+
+    // A file from before uids were random refers to items by their old uids, which are looked up in the new uids of the word and sentence files
+    pub fn load(file: &str, words: &LegacyUids, sentences: &LegacyUids) -> Result<Self, String> {
+        let mut serialized_data = Vec::new();
+        File::open(file).and_then(|mut x| x.read_to_end(&mut serialized_data)).map_err(|x| format!("Could not read {}: {}", file, x))?;
+        match utils::split_header(&serialized_data) {
+            Some((utils::FORMAT_VERSION, serialized_data)) => deserialize(serialized_data).map_err(|x| format!("Could not read {}: {}", file, x)),
+            Some((version, _)) => Err(format!("Could not read {}: it was saved in the unknown format {}", file, version)),
+            None => Ok(Self::from_old(deserialize(&serialized_data).map_err(|x| format!("Could not read {}: {}", file, x))?, words, sentences)),
         }
     }
 
@@ -301,46 +904,202 @@ impl PracticeGroupCollection {
         let _ = self.groups.remove(index);
     }
 
-    pub fn get_group_indices(&self, search_words: &Search, search_sentences: &Search) -> Vec<Vec<usize>> {
-        self.groups.iter().map(|g| g.questions.iter().map(|q| {
-            match q {
-                QuestionTemplate::Sentence(uid) => search_sentences.get_index(*uid).unwrap(),
-                QuestionTemplate::Word(uid) => search_words.get_index(*uid).unwrap(),
+    pub fn get_questions(&self, index: usize, search_words: &Search, search_sentences: &Search) -> Vec<QuestionTemplate> {
+        let group = &self.groups[index];
+        match &group.query {
+            None => group.questions.iter().filter(|q| q.exists(search_words, search_sentences)).cloned().collect(),
+            Some(query) => {
+                let now = utils::now();
+                let empty = "".to_string();
+                search_words.all_items(&Query::new_with_tags(&empty, &Language::French, u16::MAX, false, &query.tags)).iter()
+                    .filter(|item| query.matches(item, self.accuracy.get(&QuestionTemplate::Word(item.uid)), now))
+                    .map(|item| QuestionTemplate::Word(item.uid))
+                    .collect()
+            }
+        }
+    }
+
+    // Only questions of the kind being merged are carried over, through the uids their items were matched to.
+    // The uids of the other kind belong to a file that wasn't merged and could point at anything here.
+    pub fn import(&mut self, other: PracticeGroupCollection, remap: &HashMap<Uid, Uid>, words: bool, search_words: &Search, search_sentences: &Search) {
+        for mut group in other.groups {
+            group.questions = group.questions.into_iter().filter_map(|q| match q {
+                QuestionTemplate::Word(uid) if words => remap.get(&uid).map(|x| QuestionTemplate::Word(*x)),
+                QuestionTemplate::Sentence(uid) if !words => remap.get(&uid).map(|x| QuestionTemplate::Sentence(*x)),
+                _ => None,
+            }).filter(|q| q.exists(search_words, search_sentences)).collect();
+            match self.groups.iter_mut().find(|g| g.name == group.name) {
+                Some(existing) => {
+                    for q in group.questions {
+                        if !existing.questions.contains(&q) {
+                            existing.questions.push(q);
+                        }
+                    }
+                }
+                None => self.groups.push(group),
             }
-        }).collect()).collect()
+        }
     }
 
-    pub fn update_group_indices(&mut self, search_words: &Search, search_sentences: &Search, indices: Vec<Vec<usize>>) {
-        for (group, group_indices) in self.groups.iter_mut().zip(indices) {
-            for (question, index) in group.questions.iter_mut().zip(group_indices) {
-                let new = match question {
-                    QuestionTemplate::Sentence(_) => QuestionTemplate::Sentence(search_sentences.get_item_from_index(index).uid),
-                    QuestionTemplate::Word(_) => QuestionTemplate::Word(search_words.get_item_from_index(index).uid),
-                };
-                *question = new;
+    pub fn redirect(&mut self, from: &QuestionTemplate, to: &QuestionTemplate) {
+        for group in &mut self.groups {
+            if group.questions.contains(to) {
+                group.questions.retain(|q| q != from);
+            } else {
+                for q in &mut group.questions {
+                    if q == from {
+                        *q = to.clone();
+                    }
+                }
+            }
+        }
+        if let Some(from_accuracy) = self.accuracy.remove(from) {
+            let accuracy = self.accuracy.entry(to.clone()).or_default();
+            accuracy.correct += from_accuracy.correct;
+            accuracy.total += from_accuracy.total;
+            for (grade, count) in from_accuracy.mistakes {
+                *accuracy.mistakes.entry(grade).or_default() += count;
+            }
+        }
+        for session in &mut self.sessions {
+            for (q, _) in &mut session.hardest {
+                if q == from {
+                    *q = to.clone();
+                }
             }
         }
+        if let (QuestionTemplate::Word(from), QuestionTemplate::Word(to)) = (from, to) {
+            if let Some(from_accuracy) = self.gender_accuracy.remove(from) {
+                let accuracy = self.gender_accuracy.entry(*to).or_default();
+                accuracy.correct += from_accuracy.correct;
+                accuracy.total += from_accuracy.total;
+            }
+        }
+    }
+
+    pub fn groups_containing(&self, question: &QuestionTemplate) -> Vec<&str> {
+        self.groups.iter().filter(|g| g.questions.contains(question)).map(|g| g.name.as_str()).collect()
+    }
+
+    pub fn remove_question(&mut self, question: &QuestionTemplate) -> bool {
+        let mut removed = false;
+        for group in &mut self.groups {
+            let len = group.questions.len();
+            group.questions.retain(|q| q != question);
+            removed |= group.questions.len() != len;
+        }
+        removed
+    }
+
+    pub fn dangling(&self, search_words: &Search, search_sentences: &Search) -> Vec<(usize, usize)> {
+        self.groups.iter().enumerate().map(|(i, g)| {
+            (i, g.questions.iter().filter(|q| !q.exists(search_words, search_sentences)).count())
+        }).filter(|(_, count)| *count != 0).collect()
+    }
+
+    pub fn dangling_accuracy(&self, search_words: &Search, search_sentences: &Search) -> usize {
+        self.accuracy.keys().filter(|q| !q.exists(search_words, search_sentences)).count()
+    }
+
+    pub fn repair(&mut self, search_words: &Search, search_sentences: &Search) {
+        for group in &mut self.groups {
+            group.questions.retain(|q| q.exists(search_words, search_sentences));
+        }
+        self.accuracy.retain(|q, _| q.exists(search_words, search_sentences));
+    }
+
+    pub fn record_answer(&mut self, template: QuestionTemplate, grade: Grade, correct: bool) {
+        let accuracy = self.accuracy.entry(template).or_default();
+        accuracy.total += 1;
+        if correct {
+            accuracy.correct += 1;
+        }
+        if grade != Grade::Correct {
+            *accuracy.mistakes.entry(grade).or_default() += 1;
+        }
+    }
+
+    pub fn record_gender(&mut self, uid: Uid, correct: bool, streak: u32) {
+        let accuracy = self.gender_accuracy.entry(uid).or_default();
+        accuracy.total += 1;
+        if correct {
+            accuracy.correct += 1;
+        }
+        self.best_gender_streak = self.best_gender_streak.max(streak);
+    }
+
+    pub fn answered_today(&self) -> u32 {
+        let today = utils::now() / SECONDS_PER_DAY;
+        self.sessions.iter().filter(|x| x.started / SECONDS_PER_DAY == today).map(|x| x.total).sum()
     }
 
-    // pub fn from_old(old: PracticeGroupCollectionOld) -> Self {
-    //     Self { groups: old.groups.iter().map(|g| PracticeGroup::from_old(g.clone())).collect() }
-    // }
-}
-
-// impl PracticeGroupCollectionOld {
-//     pub fn load_or_new(file: &str) -> Self {
-//         match File::open(file) {
-//             Ok(mut file) => {
-//                 let mut serialized_data = Vec::new();
-//                 file.read_to_end(&mut serialized_data).unwrap();
-//                 let data: Self = deserialize(&serialized_data).unwrap();
-//                 data
-//             }
-//             Err(_) => {
-//                 Self {
-//                     groups: vec![],
-//                 }
-//             }
-//         }
-//     }
-// }
\ No newline at end of file
+    // The streak is broken once a whole day passes without meeting the goal
+    pub fn current_streak(&self) -> u32 {
+        if self.streak_day + 1 >= utils::now() / SECONDS_PER_DAY { self.streak } else { 0 }
+    }
+
+    pub fn record_session(&mut self, session: Session) {
+        self.sessions.push(session);
+        let today = utils::now() / SECONDS_PER_DAY;
+        if self.streak_day != today && self.answered_today() >= self.daily_goal.max(1) {
+            self.streak = self.current_streak() + 1;
+            self.streak_day = today;
+        }
+    }
+
+    pub fn from_old(old: PracticeGroupCollectionOld, words: &LegacyUids, sentences: &LegacyUids) -> Self {
+        Self { groups: old.groups.into_iter().map(|x| PracticeGroup::from_old(x, words, sentences)).collect(), ..Self::new() }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn loads_file_from_before_random_uids() {
+        // Written field by field the way the first release wrote its groups, a question is its variant index and the item's uid
+        let (word, sentence) = (0u32, 1u32);
+        let groups = vec![("verbs".to_string(), vec![(word, 0u32), (sentence, 3u32), (word, 5u32)])];
+        let file = std::env::temp_dir().join(format!("french-practice-legacy-{}.bin", std::process::id()));
+        let file = file.to_str().unwrap();
+        std::fs::write(file, serialize(&groups).unwrap()).unwrap();
+
+        // The word with the old uid 5 is no longer in the word file
+        let words = HashMap::from([(0, 100)]);
+        let sentences = HashMap::from([(3, 300)]);
+        let practice = PracticeGroupCollection::load(file, &words, &sentences);
+        std::fs::remove_file(file).unwrap();
+        let practice = practice.unwrap();
+        assert_eq!(practice.groups.len(), 1);
+        assert_eq!(practice.groups[0].name, "verbs");
+        assert_eq!(practice.groups[0].questions, vec![QuestionTemplate::Word(100), QuestionTemplate::Sentence(300)]);
+        assert!(practice.sessions.is_empty());
+    }
+
+    #[test]
+    fn saved_file_is_read_back_as_it_is() {
+        let file = std::env::temp_dir().join(format!("french-practice-saved-{}.bin", std::process::id()));
+        let file = file.to_str().unwrap();
+        let mut practice = PracticeGroupCollection::new();
+        practice.add_group(PracticeGroup::new_with_questions("verbs".to_string(), vec![QuestionTemplate::Word(100)]));
+        practice.save(file);
+        let loaded = PracticeGroupCollection::load(file, &HashMap::new(), &HashMap::new());
+        std::fs::remove_file(file).unwrap();
+        assert!(loaded.unwrap() == practice);
+    }
+
+    #[test]
+    fn same_seed_gives_same_question() {
+        let noun = Category::Noun(crate::search::Noun { singular: "chien".to_string(), plural: "chiens".to_string(), ..Default::default() });
+        let item = Item::new(Some("hund".to_string()), Some("dog".to_string()), noun, 1, vec![], vec![], None);
+        let settings = GroupSettings::default();
+        let question = |seed| generate_practice_question(item.clone(), &settings, &mut ChaCha8Rng::seed_from_u64(seed)).unwrap();
+        let first = question(0);
+        for seed in 0..20 {
+            assert!(question(seed) == question(seed));
+        }
+        // Both directions and both numbers are asked, so some seed must give another question
+        assert!((1..20).any(|seed| question(seed) != first));
+    }
+}
@@ -1,8 +1,9 @@
-use std::{fmt::Display, fs::File, io::{Write, Read}};
+use std::{collections::HashMap, fmt::Display, fs::File, io::{Write, Read}, path::Path};
 use levenshtein::levenshtein;
 use serde::{Serialize, Deserialize};
 use bincode::{serialize, deserialize};
-use rand::{distributions::{Distribution, Standard}, rngs::ThreadRng, seq::SliceRandom, Rng};
+use crate::utils;
+use rand::{distributions::{Distribution, Standard}, seq::SliceRandom, Rng};
 
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum Gender {
@@ -304,7 +305,89 @@ pub enum Category {
 //     Other(String),
 // }
 
+pub type Forms<'a> = Vec<(&'static str, &'a String)>;
+
 impl Category {
+    // Required forms that should never be empty, followed by optional forms that are only checked for whitespace
+    pub fn forms(&self) -> (Forms<'_>, Forms<'_>) {
+        match self {
+            Self::Noun(noun) => {
+                // Uncountable and proper nouns often have no plural
+                if noun.countable == Countability::Countable && noun.proper == ProperOrCommon::Common {
+                    (vec![("singular", &noun.singular), ("plural", &noun.plural)], vec![])
+                } else {
+                    (vec![("singular", &noun.singular)], vec![("plural", &noun.plural)])
+                }
+            }
+            Self::Verb(name, forms) => {
+                let (VerbForms::Regular(je, tu, il, nous, vous, ils, pc, imp_je, imp_tu, imp_il, imp_nous, imp_vous, imp_ils) | VerbForms::Irregular(je, tu, il, nous, vous, ils, pc, imp_je, imp_tu, imp_il, imp_nous, imp_vous, imp_ils)) = forms;
+                (vec![
+                    ("name", name), ("je form", je), ("tu form", tu), ("il form", il), ("nous form", nous), ("vous form", vous), ("ils form", ils), ("passé composé", pc),
+                    ("imparfait je form", imp_je), ("imparfait tu form", imp_tu), ("imparfait il form", imp_il), ("imparfait nous form", imp_nous), ("imparfait vous form", imp_vous), ("imparfait ils form", imp_ils),
+                ], vec![])
+            }
+            Self::Adjective(adjective) => {
+                match adjective {
+                    Adjective::Descriptive(male, female, plural_male, plural_female) |
+                    Adjective::Indefinite(male, female, plural_male, plural_female) |
+                    Adjective::ExclamativeInterrogative(male, female, plural_male, plural_female) |
+                    Adjective::Past(male, female, plural_male, plural_female) |
+                    Adjective::Present(male, female, plural_male, plural_female) |
+                    Adjective::Relative(male, female, plural_male, plural_female) => {
+                        (vec![("singular male", male), ("singular female", female), ("plural male", plural_male), ("plural female", plural_female)], vec![])
+                    }
+                    Adjective::Demonstrative(male, male_vowel, female, plural) => {
+                        (vec![("male", male), ("male before vowel", male_vowel), ("female", female), ("plural", plural)], vec![])
+                    }
+                    Adjective::Negative(male, female) => (vec![("male", male), ("female", female)], vec![]),
+                    Adjective::Possessive(male, female, plural) => (vec![("male", male), ("female", female), ("plural", plural)], vec![]),
+                }
+            }
+            Self::Article(male, female, plural, vowel) => {
+                (vec![("male", male), ("female", female), ("plural", plural)], vowel.iter().map(|x| ("form before vowel", x)).collect())
+            }
+            Self::Number(cardinal, cardinal_female, ordinal, ordinal_female, multiplicative, approximate, fraction, fraction_other) => {
+                let optional = [
+                    ("female cardinal", cardinal_female), ("female ordinal", ordinal_female), ("multiplicative", multiplicative),
+                    ("approximate", approximate), ("fraction", fraction), ("other fraction", fraction_other),
+                ];
+                (vec![("cardinal", cardinal), ("ordinal", ordinal)], optional.into_iter().filter_map(|(name, x)| x.as_ref().map(|x| (name, x))).collect())
+            }
+            Self::Pronoun(pronoun) => {
+                match pronoun {
+                    Pronoun::Adverbial(s) |
+                    Pronoun::ImpersonalSubject(s) |
+                    Pronoun::IndefiniteDemonstrative(s) |
+                    Pronoun::IndefiniteRelative(s) |
+                    Pronoun::Interrogative(s) |
+                    Pronoun::Negative(s) => (vec![("pronoun", s)], vec![]),
+                    Pronoun::Indefinite(s, other) => (vec![("pronoun", s)], other.iter().map(|x| ("other form", x)).collect()),
+                    Pronoun::Personal(a, b, c, other) => {
+                        (vec![("subject form", a), ("object form", b), ("stressed form", c)], other.iter().flat_map(|(x, y)| [("other form", x), ("other form", y)]).collect())
+                    }
+                    Pronoun::Demonstrative(a, b, c, d) |
+                    Pronoun::Possessive(a, b, c, d) => (vec![("male", a), ("female", b), ("plural male", c), ("plural female", d)], vec![]),
+                    Pronoun::Relative(s, other) => {
+                        (vec![("pronoun", s)], other.iter().flat_map(|(x, y, z)| [("other form", x), ("other form", y), ("other form", z)]).collect())
+                    }
+                }
+            }
+            Self::Conjunction(s) |
+            Self::Preposition(s) |
+            Self::Adverb(s) |
+            Self::Interjection(s) |
+            Self::Other(s) => (vec![("french", s)], vec![]),
+        }
+    }
+
+    // The french form of categories that have nothing else, which can be turned into each other without losing anything
+    pub fn single_form(&self) -> Option<&String> {
+        match self {
+            Self::Conjunction(s) | Self::Preposition(s) | Self::Adverb(s) | Self::Interjection(s) | Self::Other(s) => Some(s),
+            _ => None,
+        }
+    }
+
     pub fn to_u16(&self) -> u16 {
         match self {
             Self::Noun(..) => 0b1,
@@ -473,22 +556,46 @@ pub struct Item {
     pub english: Option<String>,
     pub category: Category,
     category_int: u16,
-    pub uid: u32,
+    pub uid: Uid,
+    pub tags: Vec<String>,
+    pub added: u64,
+    pub alternatives: Vec<String>,
+    pub ipa: Option<String>,
+    // The words a sentence is made of, always empty for words
+    pub words: Vec<Uid>,
 }
 
-// #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
-// pub struct ItemOld {
-//     pub swedish: Option<String>,
-//     pub english: Option<String>,
-//     pub category: CategoryOld,
-//     category_int: u16,
-//     pub uid: u32,
-// }
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ItemOld {
+    pub swedish: Option<String>,
+    pub english: Option<String>,
+    pub category: Category,
+    category_int: u16,
+    pub uid: u32,
+}
 
 impl Item {
-    pub fn new(swedish: Option<String>, english: Option<String>, category: Category, uid: u32) -> Self {
+    pub fn new(swedish: Option<String>, english: Option<String>, category: Category, uid: Uid, tags: Vec<String>, alternatives: Vec<String>, ipa: Option<String>) -> Self {
         let category_int = category.to_u16();
-        Self { swedish, english, category, category_int, uid }
+        Self { swedish, english, category, category_int, uid, tags, added: utils::now(), alternatives, ipa, words: vec![] }
+    }
+
+    pub fn set_category(&mut self, category: Category) {
+        self.category_int = category.to_u16();
+        self.category = category;
+    }
+
+    pub fn same_content(&self, other: &Item) -> bool {
+        self.swedish == other.swedish && self.english == other.english && self.category == other.category && self.tags == other.tags && self.alternatives == other.alternatives && self.ipa == other.ipa && self.words == other.words
+    }
+
+    // The swedish translation followed by any alternative translations
+    pub fn swedish_translations(&self) -> Vec<&String> {
+        self.swedish.iter().chain(&self.alternatives).collect()
+    }
+
+    pub fn has_tags(&self, tags: &[String]) -> bool {
+        tags.iter().all(|tag| self.tags.contains(tag))
     }
 
     fn language_strings(&self, language: &Language) -> Option<Vec<&String>> {
@@ -580,12 +687,7 @@ impl Item {
                     }
                 }
             }
-            Language::Swedish => {
-                match &self.swedish {
-                    None => None,
-                    Some(string) => Some(vec![string]),
-                }
-            }
+            Language::Swedish => self.swedish.as_ref().map(|_| self.swedish_translations()),
             Language::English => {
                 match &self.english {
                     None => None,
@@ -654,12 +756,38 @@ impl Item {
     }
 
     pub fn tooltip(&self) -> String {
-        self.category.display_detailed(&self.english, &self.swedish)
+        let tooltip = self.category.display_detailed(&self.english, &self.swedish);
+        match &self.ipa {
+            Some(ipa) => format!("{} /{}/", tooltip, ipa),
+            None => tooltip,
+        }
     }
 
-    // fn from_old(old: ItemOld) -> Self {
-    //     Self { swedish: old.swedish, english: old.english, category: Category::from_old(old.category), category_int: old.category_int, uid: old.uid }
-    // }
+    // When an item from before dates were kept was added is unknown, so it counts as old
+    fn from_old(old: ItemOld, uid: Uid) -> Self {
+        Self { swedish: old.swedish, english: old.english, category: old.category, category_int: old.category_int, uid, tags: vec![], added: 0, alternatives: vec![], ipa: None, words: vec![] }
+    }
+}
+
+pub fn parse_alternatives(string: &str) -> Vec<String> {
+    string.split(',').map(|x| x.trim().to_string()).filter(|x| !x.is_empty()).collect()
+}
+
+// Slashes around the transcription are only notation and are shown by the ui
+pub fn parse_ipa(string: &str) -> Option<String> {
+    let ipa = string.trim().trim_matches('/').trim();
+    (!ipa.is_empty()).then(|| ipa.to_string())
+}
+
+pub fn parse_tags(string: &str) -> Vec<String> {
+    let mut tags: Vec<String> = vec![];
+    for tag in string.split(',') {
+        let tag = tag.trim().to_lowercase();
+        if !tag.is_empty() && !tags.contains(&tag) {
+            tags.push(tag);
+        }
+    }
+    tags
 }
 
 #[derive(Debug, PartialEq)]
@@ -703,25 +831,39 @@ pub struct Query<'a> {
     pub language: &'a Language,
     pub search_categories_int: u16,
     pub match_length: bool,
+    pub tags: &'a [String],
 }
 
 impl<'a> Query<'a> {
     pub fn new(string: &'a String, language: &'a Language, search_categories_int: u16, match_length: bool) -> Self {
-        Self { string, language, search_categories_int, match_length }
+        Self { string, language, search_categories_int, match_length, tags: &[] }
+    }
+
+    pub fn new_with_tags(string: &'a String, language: &'a Language, search_categories_int: u16, match_length: bool, tags: &'a [String]) -> Self {
+        Self { string, language, search_categories_int, match_length, tags }
+    }
+
+    fn matches(&self, item: &Item) -> bool {
+        item.category_int & self.search_categories_int != 0 && item.has_tags(self.tags)
     }
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+pub type Uid = u64;
+// The uids items had before uids were random, mapped to the ones they were given
+pub type LegacyUids = HashMap<u32, Uid>;
+
+#[derive(Debug, Default, Serialize, Deserialize)]
 pub struct Search {
     items: Vec<Item>,
-    uid_counter: u32,
+    #[serde(skip)]
+    index: HashMap<Uid, usize>,
 }
 
-// #[derive(Debug, Serialize, Deserialize)]
-// pub struct SearchOld {
-//     items: Vec<ItemOld>,
-//     uid_counter: u32,
-// }
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SearchOld {
+    items: Vec<ItemOld>,
+    uid_counter: u32,
+}
 
 impl Search {
     pub fn get_all(&self, query: &Query, num_answers: usize) -> Vec<(String, Item)> {
@@ -729,7 +871,7 @@ impl Search {
 
         'outer: for item in &self.items {
             if let Some(string) = item.language_string(&query.language) {
-                if item.category_int & query.search_categories_int != 0 {
+                if query.matches(item) {
                     matches.push((string, item.clone()));
                     if matches.len() == num_answers {
                         break 'outer;
@@ -745,7 +887,7 @@ impl Search {
         let mut matches = vec![];
 
         for item in &self.items {
-            if item.category_int & query.search_categories_int != 0 {
+            if query.matches(item) {
                 matches.push(item);
             }
         }
@@ -760,7 +902,7 @@ impl Search {
 
         for item in &self.items {
             if let Some(strings) = item.language_strings(&query.language) {
-                if item.category_int & query.search_categories_int != 0 {
+                if query.matches(item) {
                     for string in strings {
                         let list_item = (string, item.clone());
                         if ((query.match_length && length == string.len()) || !query.match_length) && !best_matches.contains(&list_item) {
@@ -791,7 +933,7 @@ impl Search {
 
         for item in &self.items {
             if let Some(strings) = item.language_strings(&query.language) {
-                if item.category_int & query.search_categories_int != 0 {
+                if query.matches(item) {
                     for string in strings {
                         let list_item = (string, item.clone());
                         if ((query.match_length && length == string.len()) || !query.match_length) && !best_matches.contains(&list_item) {
@@ -812,30 +954,56 @@ impl Search {
         (best_matches.iter().map(|(s, x)| (s.to_owned().to_owned(), x.to_owned())).collect(), best_match_score)
     }
 
-    pub fn add_item(&mut self, item: Item) {
+    pub fn add_item(&mut self, item: Item) -> Result<(), ()> {
+        if self.index.contains_key(&item.uid) {
+            return Err(());
+        }
+        self.index.insert(item.uid, self.items.len());
         self.items.push(item);
+        Ok(())
+    }
+
+    fn reindex(&mut self) {
+        self.index = self.items.iter().enumerate().map(|(i, x)| (x.uid, i)).collect();
+    }
+
+    // Only possible in a corrupted file, where practice groups and links can't tell the two items apart
+    fn duplicate_uid(&self) -> Option<Uid> {
+        self.items.iter().enumerate().find(|(i, x)| self.index[&x.uid] != *i).map(|(_, x)| x.uid)
     }
 
     pub fn save(&self, file: &str) {
-        let serialized_data = serialize(self).unwrap();
+        let serialized_data = utils::with_header(serialize(self).unwrap());
         let mut file = File::create(file).unwrap();
         file.write_all(&serialized_data).unwrap();
     }
 
-    pub fn load_or_new(file: &str) -> Self {
-        match File::open(file) {
-            Ok(mut file) => {
-                let mut serialized_data = Vec::new();
-                file.read_to_end(&mut serialized_data).unwrap();
-                let data: Self = deserialize(&serialized_data).unwrap();
-                data
-            }
-            Err(_) => {
-                Self {
-                    items: vec![],
-                    uid_counter: 0,
+    pub fn load_or_new(file: &str) -> Result<(Self, Option<LegacyUids>), String> {
+        if !Path::new(file).exists() {
+            return Ok((Self::default(), None));
+        }
+        Self::load(file)
+    }
+
+    // The new uids are only returned for a file from before uids were random, so references to its items can be remapped
+    pub fn load(file: &str) -> Result<(Self, Option<LegacyUids>), String> {
+        let mut serialized_data = Vec::new();
+        File::open(file).and_then(|mut x| x.read_to_end(&mut serialized_data)).map_err(|x| format!("Could not read {}: {}", file, x))?;
+        match utils::split_header(&serialized_data) {
+            Some((utils::FORMAT_VERSION, serialized_data)) => {
+                let mut data: Self = deserialize(serialized_data).map_err(|x| format!("Could not read {}: {}", file, x))?;
+                data.reindex();
+                match data.duplicate_uid() {
+                    Some(uid) => Err(format!("Could not read {}: the uid {} is used more than once", file, uid)),
+                    None => Ok((data, None)),
                 }
             }
+            Some((version, _)) => Err(format!("Could not read {}: it was saved in the unknown format {}", file, version)),
+            None => {
+                let old: SearchOld = deserialize(&serialized_data).map_err(|x| format!("Could not read {}: {}", file, x))?;
+                let (data, uids) = Self::from_old(old).map_err(|x| format!("Could not read {}: {}", file, x))?;
+                Ok((data, Some(uids)))
+            }
         }
     }
 
@@ -843,23 +1011,22 @@ impl Search {
     //     self.items.iter().position(|x| x == item).unwrap()
     // }
 
-    pub fn remove_item(&mut self, uid: u32) -> Result<(), ()> {
+    pub fn remove_item(&mut self, uid: Uid) -> Result<(), ()> {
         match self.get_index(uid) {
             Some(index) => {
                 self.items.remove(index);
+                self.reindex();
                 Ok(())
             }
             None => Err(())
         }
     }
 
-    pub fn get_item_from_index(&self, index: usize) -> Item {
-        self.items[index].clone()
-    }
-
-    pub fn edit_item(&mut self, uid: u32, new_item: Item) -> Result<(), ()> {
+    pub fn edit_item(&mut self, uid: Uid, mut new_item: Item) -> Result<(), ()> {
         match self.get_index(uid) {
             Some(index) => {
+                new_item.uid = uid;
+                new_item.added = self.items[index].added;
                 self.items[index] = new_item;
                 Ok(())
             }
@@ -867,11 +1034,11 @@ impl Search {
         }
     }
 
-    pub fn get_index(&self, uid: u32) -> Option<usize> {
-        self.items.binary_search_by_key(&uid, |item| item.uid).ok()
+    pub fn get_index(&self, uid: Uid) -> Option<usize> {
+        self.index.get(&uid).copied()
     }
 
-    pub fn get_item(&self, uid: u32) -> Option<Item> {
+    pub fn get_item(&self, uid: Uid) -> Option<Item> {
         // for item in &self.items {
         //     if item.uid == uid {
         //         return Some(item.clone());
@@ -884,28 +1051,78 @@ impl Search {
         }
     }
 
-    pub fn random_item(&self, query: &Query, rng: &mut ThreadRng) -> Item {
-        (**self.all_items(query).choose(rng).unwrap()).clone()
+    pub fn random_item(&self, query: &Query, accept: impl Fn(&Item) -> bool, rng: &mut impl Rng) -> Option<Item> {
+        self.all_items(query).into_iter().filter(|x| accept(x)).collect::<Vec<&Item>>().choose(rng).map(|x| (*x).clone())
     }
 
-    pub fn next_uid(&mut self) -> u32 {
-        let out = self.uid_counter;
-        self.uid_counter += 1;
-        out
+    pub fn new_uid(&self) -> Uid {
+        let mut rng = rand::thread_rng();
+        loop {
+            let uid = rng.gen();
+            if !self.index.contains_key(&uid) {
+                return uid;
+            }
+        }
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Item> {
+        self.items.iter()
+    }
+
+    pub fn linking(&self, uid: Uid) -> Vec<&Item> {
+        self.items.iter().filter(|x| x.words.contains(&uid)).collect()
+    }
+
+    // Points every link to one word at another, returning the items before and after the change
+    pub fn redirect_words(&mut self, from: Uid, to: Uid) -> Vec<(Item, Item)> {
+        let mut changes = vec![];
+        for item in self.items.iter_mut().filter(|x| x.words.contains(&from)) {
+            let old = item.clone();
+            let mut words = vec![];
+            for uid in old.words.iter().map(|x| if *x == from { to } else { *x }) {
+                if !words.contains(&uid) {
+                    words.push(uid);
+                }
+            }
+            item.words = words;
+            changes.push((old, item.clone()));
+        }
+        changes
     }
 
-    pub fn recreate_uids(&mut self) {
-        let mut uid_counter = 0;
-        for item in &mut self.items {
-            item.uid = uid_counter;
-            uid_counter += 1;
+    pub fn all_tags(&self) -> Vec<String> {
+        let mut tags: Vec<String> = vec![];
+        for item in &self.items {
+            for tag in &item.tags {
+                if !tags.contains(tag) {
+                    tags.push(tag.clone());
+                }
+            }
         }
-        self.uid_counter = uid_counter;
+        tags.sort();
+        tags
     }
 
-    // pub fn from_old(old: SearchOld) -> Self {
-    //     Self { items: old.items.iter().map(|x| Item::from_old(x.to_owned())).collect(), uid_counter: old.uid_counter }
-    // }
+    pub fn subset(&self, uids: &[Uid]) -> Self {
+        let mut search = Self { items: self.items.iter().filter(|x| uids.contains(&x.uid)).cloned().collect(), index: HashMap::new() };
+        search.reindex();
+        search
+    }
+
+    // Old uids were numbered from 0 in every file, so each item gets a fresh random uid instead
+    pub fn from_old(old: SearchOld) -> Result<(Self, LegacyUids), String> {
+        let mut search = Self { items: vec![], index: HashMap::new() };
+        let mut uids = HashMap::new();
+        for item in old.items {
+            let uid = search.new_uid();
+            if uids.insert(item.uid, uid).is_some() {
+                return Err(format!("the uid {} is used more than once", item.uid));
+            }
+            // A fresh uid is never taken
+            let _ = search.add_item(Item::from_old(item, uid));
+        }
+        Ok((search, uids))
+    }
 }
 
 // impl SearchOld {
@@ -922,4 +1139,78 @@ impl Search {
 //             }
 //         }
 //     }
-// }
\ No newline at end of file
+// }
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn loads_file_from_before_random_uids() {
+        // Written field by field the way the first release wrote its items, with sequential uids and a uid counter
+        let noun = Category::Noun(Noun { singular: "chien".to_string(), plural: "chiens".to_string(), ..Noun::default() });
+        let items = vec![
+            (Some("hund".to_string()), None::<String>, noun.clone(), noun.to_u16(), 0u32),
+            (Some("och".to_string()), Some("and".to_string()), Category::Conjunction("et".to_string()), Category::Conjunction("".to_string()).to_u16(), 1u32),
+        ];
+        let file = std::env::temp_dir().join(format!("french-legacy-{}.bin", std::process::id()));
+        let file = file.to_str().unwrap();
+        std::fs::write(file, serialize(&(items, 2u32)).unwrap()).unwrap();
+
+        let (search, uids) = Search::load(file).unwrap();
+        let uids = uids.unwrap();
+        assert_eq!(uids.len(), 2);
+        let dog = search.get_item(uids[&0]).unwrap();
+        assert_eq!(dog.swedish.as_deref(), Some("hund"));
+        assert_eq!(dog.category, noun);
+        assert!(dog.tags.is_empty());
+        let and = search.get_item(uids[&1]).unwrap();
+        assert_eq!(and.english.as_deref(), Some("and"));
+        assert_eq!(search.all_items(&Query::new(&"".to_string(), &Language::French, 0b1, false)).len(), 1);
+
+        // Once saved the file is read as it is, without new uids
+        search.save(file);
+        let (reloaded, uids_after) = Search::load(file).unwrap();
+        std::fs::remove_file(file).unwrap();
+        assert!(uids_after.is_none());
+        assert_eq!(reloaded.get_item(uids[&1]), Some(and));
+    }
+
+    #[test]
+    fn duplicate_uid_is_an_error() {
+        let item = Item::new(Some("och".to_string()), None, Category::Conjunction("et".to_string()), 7, vec![], vec![], None);
+        let mut search = Search { items: vec![], index: HashMap::new() };
+        assert!(search.add_item(item.clone()).is_ok());
+        assert!(search.add_item(item.clone()).is_err());
+
+        let file = std::env::temp_dir().join(format!("french-duplicate-{}.bin", std::process::id()));
+        let file = file.to_str().unwrap();
+        Search { items: vec![item.clone(), item], index: HashMap::new() }.save(file);
+        let result = Search::load(file);
+        std::fs::remove_file(file).unwrap();
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn unknown_format_is_an_error() {
+        let file = std::env::temp_dir().join(format!("french-format-{}.bin", std::process::id()));
+        let file = file.to_str().unwrap();
+        Search::default().save(file);
+        let mut data = std::fs::read(file).unwrap();
+        assert!(utils::split_header(&data).is_some_and(|(version, _)| version == utils::FORMAT_VERSION));
+        data[utils::MAGIC.len()] += 1;
+        std::fs::write(file, data).unwrap();
+        let result = Search::load(file);
+        std::fs::remove_file(file).unwrap();
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn unreadable_file_is_an_error() {
+        let file = std::env::temp_dir().join(format!("french-garbage-{}.bin", std::process::id()));
+        let file = file.to_str().unwrap();
+        std::fs::write(file, [1, 2, 3]).unwrap();
+        let result = Search::load(file);
+        std::fs::remove_file(file).unwrap();
+        assert!(result.is_err());
+    }
+}
@@ -1,33 +1,421 @@
-use rand::seq::SliceRandom;
-use crate::search::{Category, Item, Language, Pronoun, Query, Search, VerbForms};
+use rand::{seq::SliceRandom, Rng};
+use crate::{conjugation::{self, Tense, TENSES}, phonetics, search::{Adjective, Category, Countability, Gender, Item, Language, Pronoun, ProperOrCommon, Query, Search}};
+
+const NOUN: u16 = 0b1;
+const VERB: u16 = 0b10;
+const ADJECTIVE: u16 = 0b100;
+const ARTICLE: u16 = 0b10000;
+const PREPOSITION: u16 = 0b10000000;
+const PRONOUN: u16 = 0b100000000;
+
+// Subject pronouns with their person and whether they are feminine
+const PRONOUNS: [(&str, usize, bool); 9] = [("je", 0, false), ("tu", 1, false), ("il", 2, false), ("elle", 2, true), ("on", 2, false), ("nous", 3, false), ("vous", 4, false), ("ils", 5, false), ("elles", 5, true)];
+// Adjectives of beauty, age, goodness and size usually go before the noun
+const BEFORE_NOUN: [&str; 15] = ["beau", "bon", "grand", "gros", "haut", "jeune", "joli", "long", "mauvais", "nouveau", "petit", "vieux", "premier", "dernier", "autre"];
+const CONTRACTIONS: [(&str, &str, &str); 4] = [("à", "le", "au"), ("à", "les", "aux"), ("de", "le", "du"), ("de", "les", "des")];
+
+#[derive(Clone, PartialEq)]
+pub struct Word {
+    pub french: String,
+    // Grammatical words like ne and pas don't come from the word list
+    pub item: Option<Item>,
+    // What the form adds to the meaning of the item, like the tense or plural
+    note: Option<String>,
+    // Written together with the word before, like the -t-il of an inverted question
+    glue: bool,
+}
+
+impl Word {
+    fn new(french: &str, item: Option<&Item>, note: Option<String>) -> Self {
+        Self { french: french.to_string(), item: item.cloned(), note, glue: false }
+    }
+}
+
+#[derive(Clone, PartialEq)]
+pub struct Sentence {
+    pub words: Vec<Word>,
+    pub verb: Item,
+    pub tense: Tense,
+    negated: bool,
+    question: bool,
+}
+
+impl Sentence {
+    pub fn french(&self) -> String {
+        let mut string = String::new();
+        for word in &self.words {
+            if !string.is_empty() && !word.glue && !string.ends_with('\'') {
+                string.push(' ');
+            }
+            string.push_str(&word.french);
+        }
+        string
+    }
+
+    // The translations of the words in french order, for the user to turn into a real sentence
+    pub fn skeleton(&self, language: &Language) -> String {
+        let (question, negated) = match language {
+            Language::Swedish => ("fråga", "nekande"),
+            _ => ("question", "negated"),
+        };
+        let features: Vec<&str> = [(self.question, question), (self.negated, negated)].into_iter().filter(|(x, _)| *x).map(|(_, x)| x).collect();
+        let glosses: Vec<String> = self.words.iter().filter_map(|word| {
+            let item = word.item.as_ref()?;
+            let gloss = item.language_string(language).cloned().unwrap_or_else(|| word.french.clone());
+            Some(match &word.note {
+                Some(note) => format!("{} [{}]", gloss, note),
+                None => gloss,
+            })
+        }).collect();
+        if features.is_empty() {
+            glosses.join(" ")
+        } else {
+            format!("({}) {}", features.join(", "), glosses.join(" "))
+        }
+    }
+}
+
+static EMPTY: String = String::new();
+
+fn query(categories: u16) -> Query<'static> {
+    Query::new(&EMPTY, &Language::French, categories, false)
+}
+
+fn starts_with_vowel(words: &[Word]) -> bool {
+    words.first().is_some_and(|x| phonetics::starts_with_vowel_sound(&x.french))
+}
+
+// An article, the noun and maybe an adjective that agrees with it, along with the gender and whether it is plural
+fn noun_phrase(words: &Search, noun: Option<Item>, negated: bool, rng: &mut impl Rng) -> Option<(Vec<Word>, Gender, bool)> {
+    let common = |x: &Item| matches!(&x.category, Category::Noun(noun) if noun.proper == ProperOrCommon::Common && !noun.singular.is_empty());
+    let item = match noun {
+        Some(item) => item,
+        None => words.random_item(&query(NOUN), common, rng)?,
+    };
+    let Category::Noun(noun) = &item.category else {
+        return None;
+    };
+    let plural = noun.countable == Countability::Countable && !noun.plural.is_empty() && rng.gen_bool(0.3);
+    let article = words.random_item(&query(ARTICLE), |x| matches!(&x.category, Category::Article(male, ..) if !male.is_empty()), rng)?;
+    let Category::Article(male, female, plural_article, vowel) = &article.category else {
+        return None;
+    };
+    let mut phrase = vec![Word::new(if plural { &noun.plural } else { &noun.singular }, Some(&item), plural.then(|| "plural".to_string()))];
+    let descriptive = |x: &Item| matches!(&x.category, Category::Adjective(Adjective::Descriptive(male, ..)) if !male.is_empty());
+    if rng.gen_bool(0.5) {
+        if let Some(adjective) = words.random_item(&query(ADJECTIVE), descriptive, rng) {
+            let Category::Adjective(Adjective::Descriptive(s_m, s_f, p_m, p_f)) = &adjective.category else {
+                return None;
+            };
+            let form = match (&noun.gender, plural) {
+                (Gender::Male, false) => s_m,
+                (Gender::Female, false) => s_f,
+                (Gender::Male, true) => p_m,
+                (Gender::Female, true) => p_f,
+            };
+            let word = Word::new(form, Some(&adjective), None);
+            if BEFORE_NOUN.contains(&s_m.as_str()) {
+                phrase.insert(0, word);
+            } else {
+                phrase.push(word);
+            }
+        }
+    }
+    // Un, une and des become de after a negation
+    let article_form = if negated && matches!(male.as_str(), "un" | "du") {
+        "de"
+    } else if plural {
+        plural_article.as_str()
+    } else {
+        match (vowel, starts_with_vowel(&phrase)) {
+            (Some(vowel), true) if !vowel.is_empty() => vowel.as_str(),
+            _ if noun.gender == Gender::Male => male.as_str(),
+            _ => female.as_str(),
+        }
+    };
+    phrase.insert(0, Word::new(article_form, Some(&article), None));
+    Some((phrase, noun.gender.clone(), plural))
+}
+
+fn prepositional_phrase(words: &Search, rng: &mut impl Rng) -> Option<Vec<Word>> {
+    let preposition = words.random_item(&query(PREPOSITION), |x| matches!(&x.category, Category::Preposition(string) if !string.is_empty()), rng)?;
+    let Category::Preposition(string) = &preposition.category else {
+        return None;
+    };
+    let (mut phrase, ..) = noun_phrase(words, None, false, rng)?;
+    let mut preposition_word = Word::new(string, Some(&preposition), None);
+    // À le and de les are always contracted, but not in front of l'
+    if let Some((.., contraction)) = CONTRACTIONS.iter().find(|(x, article, _)| *x == string.as_str() && *article == phrase[0].french) {
+        if phrase[0].french == "les" || !starts_with_vowel(&phrase[1..]) {
+            preposition_word.french = contraction.to_string();
+            phrase.remove(0);
+        }
+    }
+    phrase.insert(0, preposition_word);
+    Some(phrase)
+}
+
+// Le, de, ne, que and the like lose their vowel in front of another vowel
+fn elide(words: &mut [Word]) {
+    for i in 1..words.len() {
+        let last = words[i - 1].french.rsplit(' ').next().unwrap_or_default().to_lowercase();
+        if !words[i].glue && phonetics::elides(&last) && phonetics::starts_with_vowel_sound(&words[i].french) {
+            words[i - 1].french.pop();
+            words[i - 1].french.push('\'');
+        }
+    }
+}
+
+pub fn generate(words: &Search, subject: Option<Item>, verb: Option<Item>, tense: Option<Tense>, rng: &mut impl Rng) -> Option<Sentence> {
+    let negated = rng.gen_bool(0.3);
+    let question = rng.gen_bool(0.3);
+
+    let personal = |x: &Item| matches!(&x.category, Category::Pronoun(Pronoun::Personal(subject, ..)) if PRONOUNS.iter().any(|(p, ..)| *p == subject.as_str()));
+    let subject = match subject {
+        Some(subject) => Some(subject),
+        None if rng.gen_bool(0.5) => words.random_item(&query(NOUN), |x| matches!(&x.category, Category::Noun(noun) if noun.proper == ProperOrCommon::Common), rng),
+        None => None,
+    }.filter(|x| personal(x) || matches!(x.category, Category::Noun(..))).or_else(|| words.random_item(&query(PRONOUN), personal, rng));
+    let pronoun = match &subject {
+        Some(Item { category: Category::Pronoun(Pronoun::Personal(pronoun, ..)), .. }) => Some(pronoun.clone()),
+        _ => None,
+    };
+    // The subject words, the person and whether the subject is feminine
+    let (subject_words, person, feminine) = match &pronoun {
+        Some(pronoun) => {
+            let (_, person, feminine) = *PRONOUNS.iter().find(|(p, ..)| *p == pronoun.as_str()).unwrap();
+            (vec![Word::new(pronoun, subject.as_ref(), None)], person, feminine)
+        }
+        None => {
+            let (phrase, gender, plural) = noun_phrase(words, subject, false, rng)?;
+            (phrase, if plural { 5 } else { 2 }, gender == Gender::Female)
+        }
+    };
+
+    let verb = match verb {
+        Some(verb) => verb,
+        None => words.random_item(&query(VERB), |x| matches!(x.category, Category::Verb(..)), rng)?,
+    };
+    let Category::Verb(infinitive, forms) = &verb.category else {
+        return None;
+    };
+    let tenses: Vec<Tense> = tense.map_or(TENSES.to_vec(), |x| vec![x]).into_iter().filter(|x| conjugation::conjugate(infinitive, forms, *x).is_some()).collect();
+    let tense = *tenses.choose(rng)?;
+    let mut form = conjugation::conjugate(infinitive, forms, tense)?[person].clone();
+    // The participle agrees with the subject when the auxiliary is être
+    if tense == Tense::PasseCompose && feminine && conjugation::uses_etre(infinitive) {
+        match form.strip_suffix('s') {
+            Some(stem) => form = format!("{}es", stem),
+            None => form.push('e'),
+        }
+    }
+    // In the passé composé the auxiliary is conjugated and the participle carries the meaning
+    let (finite, participle) = match form.split_once(' ') {
+        Some((auxiliary, participle)) => (Word::new(auxiliary, None, None), Some(Word::new(participle, Some(&verb), Some(tense.to_string())))),
+        None => (Word::new(&form, Some(&verb), Some(tense.to_string())), None),
+    };
 
-pub fn generate(words: &Search, subject: Option<Item>, verb: Option<Item>) -> Vec<(String, Item)> {
-    let mut rng = rand::thread_rng();
     let mut sentence = vec![];
-    let subject = subject.unwrap_or_else(|| words.random_item(&Query::new(&"".to_string(), &Language::French, 0b100000000, false), &mut rng));
-    let verb = verb.unwrap_or_else(|| words.random_item(&Query::new(&"".to_string(), &Language::French, 0b10, false), &mut rng));
-    
-    if let Category::Pronoun(Pronoun::Personal(pronoun, _, _, _)) = &subject.category {
-        sentence.push((pronoun.clone(), subject.clone()));
-        if let Category::Verb(_, forms) = &verb.category {
-            let (VerbForms::Regular(je, tu, il, nous, vous, ils, pc, imp_je, imp_tu, imp_il, imp_nous, imp_vous, imp_ils) | VerbForms::Irregular(je, tu, il, nous, vous, ils, pc, imp_je, imp_tu, imp_il, imp_nous, imp_vous, imp_ils)) = &forms;
-            sentence.push(((**match pronoun.as_str() {
-                "je" => vec![je, imp_je],
-                "tu" => vec![tu, imp_tu],
-                "nous" => vec![nous, imp_nous],
-                "vous" => vec![vous, imp_vous],
-                "ils" | "elles" => vec![ils, imp_ils],
-                _ => vec![il, imp_il],
-            }.choose(&mut rng).unwrap()).clone(), verb));
-        }
-    }
-    
-    if sentence.len() != 0 {
-        let first = sentence[0].0.remove(0).to_uppercase().next().unwrap();
-        sentence[0].0.insert(0, first);
-        let index = sentence.len()-1;
-        sentence[index].0 += ".";
-    }
-
-    sentence
-}
\ No newline at end of file
+    // Inverting je sounds stilted, and nouns would need the complex inversion, so those use est-ce que
+    let inverted = question && pronoun.as_ref().is_some_and(|x| x != "je") && rng.gen_bool(0.5);
+    if question && !inverted {
+        sentence.push(Word::new("est-ce que", None, None));
+    }
+    if !inverted {
+        sentence.extend(subject_words.iter().cloned());
+    }
+    if negated {
+        sentence.push(Word::new("ne", None, None));
+    }
+    let ends_with_vowel = finite.french.ends_with(['a', 'e']);
+    sentence.push(finite);
+    if inverted {
+        let mut pronoun = subject_words[0].clone();
+        pronoun.french = if ends_with_vowel && matches!(pronoun.french.as_str(), "il" | "elle" | "on") { format!("-t-{}", pronoun.french) } else { format!("-{}", pronoun.french) };
+        pronoun.glue = true;
+        sentence.push(pronoun);
+    }
+    if negated {
+        sentence.push(Word::new("pas", None, None));
+    }
+    sentence.extend(participle);
+    // Verbs taking être are verbs of motion and change, which take no direct object
+    if !conjugation::uses_etre(infinitive) && rng.gen_bool(0.6) {
+        if let Some((object, ..)) = noun_phrase(words, None, negated, rng) {
+            sentence.extend(object);
+        }
+    }
+    if rng.gen_bool(0.4) {
+        if let Some(phrase) = prepositional_phrase(words, rng) {
+            sentence.extend(phrase);
+        }
+    }
+
+    elide(&mut sentence);
+    if let Some(first) = sentence.first_mut() {
+        let mut chars = first.french.chars();
+        first.french = chars.next().map(|x| x.to_uppercase().chain(chars).collect()).unwrap_or_default();
+    }
+    if let Some(last) = sentence.last_mut() {
+        last.french.push_str(if question { " ?" } else { "." });
+    }
+    Some(Sentence { words: sentence, verb, tense, negated, question })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::search::{Noun, Uid, VerbForms};
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+
+    fn vocabulary(categories: Vec<Category>) -> Search {
+        let mut words = Search::default();
+        for (uid, category) in categories.into_iter().enumerate() {
+            words.add_item(Item::new(Some("".to_string()), None, category, uid as Uid + 1, vec![], vec![], None)).unwrap();
+        }
+        words
+    }
+
+    fn noun(singular: &str, plural: &str, gender: Gender) -> Category {
+        Category::Noun(Noun { singular: singular.to_string(), plural: plural.to_string(), gender, ..Noun::default() })
+    }
+
+    fn verb(infinitive: &str) -> Category {
+        let (je, tu, il, nous, vous, ils, pc, imp_je, imp_tu, imp_il, imp_nous, imp_vous, imp_ils) = VerbForms::gen_from_regular(infinitive);
+        Category::Verb(infinitive.to_string(), VerbForms::Regular(je, tu, il, nous, vous, ils, pc, imp_je, imp_tu, imp_il, imp_nous, imp_vous, imp_ils))
+    }
+
+    fn adjective(forms: [&str; 4]) -> Category {
+        let [s_m, s_f, p_m, p_f] = forms.map(|x| x.to_string());
+        Category::Adjective(Adjective::Descriptive(s_m, s_f, p_m, p_f))
+    }
+
+    fn article(male: &str, female: &str, plural: &str, vowel: Option<&str>) -> Category {
+        Category::Article(male.to_string(), female.to_string(), plural.to_string(), vowel.map(|x| x.to_string()))
+    }
+
+    fn pronoun(subject: &str) -> Category {
+        Category::Pronoun(Pronoun::Personal(subject.to_string(), "".to_string(), "".to_string(), None))
+    }
+
+    // Enough sentences in the present that every random choice is made both ways
+    fn sentences(words: &Search) -> Vec<String> {
+        (0..1000).filter_map(|seed| generate(words, None, None, Some(Tense::Present), &mut ChaCha8Rng::seed_from_u64(seed))).map(|x| x.french().to_lowercase()).collect()
+    }
+
+    fn pairs(sentence: &str) -> Vec<(&str, &str)> {
+        let words: Vec<&str> = sentence.split(' ').map(|x| x.trim_end_matches('.')).collect();
+        words.windows(2).map(|x| (x[0], x[1])).collect()
+    }
+
+    fn any_contains(sentences: &[String], text: &str) -> bool {
+        sentences.iter().any(|x| x.contains(text))
+    }
+
+    #[test]
+    fn same_seed_gives_same_sentence() {
+        let words = vocabulary(vec![noun("chien", "chiens", Gender::Male), verb("parler"), article("le", "la", "les", Some("l'")), pronoun("il")]);
+        let sentence = |seed| generate(&words, None, None, None, &mut ChaCha8Rng::seed_from_u64(seed)).unwrap().french();
+        let first = sentence(0);
+        for seed in 0..20 {
+            assert_eq!(sentence(seed), sentence(seed));
+        }
+        assert!((1..20).any(|seed| sentence(seed) != first));
+    }
+
+    #[test]
+    fn adjectives_agree_with_their_noun() {
+        let words = vocabulary(vec![
+            noun("maison", "maisons", Gender::Female),
+            adjective(["petit", "petite", "petits", "petites"]),
+            adjective(["vert", "verte", "verts", "vertes"]),
+            article("le", "la", "les", Some("l'")),
+            verb("regarder"),
+            pronoun("il"),
+        ]);
+        let sentences = sentences(&words);
+        for sentence in &sentences {
+            for (first, second) in pairs(sentence) {
+                let agreeing = match (first, second) {
+                    ("maison", adjective) | (adjective, "maison") if adjective.starts_with("petit") || adjective.starts_with("vert") => ["petite", "verte"].contains(&adjective),
+                    ("maisons", adjective) | (adjective, "maisons") if adjective.starts_with("petit") || adjective.starts_with("vert") => ["petites", "vertes"].contains(&adjective),
+                    _ => true,
+                };
+                assert!(agreeing, "{}", sentence);
+            }
+        }
+        for text in ["la petite maison", "les petites maisons", "la maison verte", "les maisons vertes"] {
+            assert!(any_contains(&sentences, text), "{}", text);
+        }
+    }
+
+    #[test]
+    fn indefinite_articles_become_de_after_negation() {
+        let words = vocabulary(vec![
+            noun("livre", "livres", Gender::Male),
+            noun("arbre", "arbres", Gender::Male),
+            article("un", "une", "des", None),
+            verb("regarder"),
+            pronoun("il"),
+        ]);
+        let sentences = sentences(&words);
+        for sentence in &sentences {
+            assert!(!pairs(sentence).iter().any(|x| x.0 == "pas" && ["un", "une", "des"].contains(&x.1)), "{}", sentence);
+        }
+        for text in ["ne regarde pas de livre", "ne regarde pas de livres", "ne regarde pas d'arbre", "regarde un livre", "regarde des livres"] {
+            assert!(any_contains(&sentences, text), "{}", text);
+        }
+    }
+
+    #[test]
+    fn vowels_elide_the_word_before() {
+        let words = vocabulary(vec![
+            noun("arbre", "arbres", Gender::Male),
+            article("le", "la", "les", Some("l'")),
+            verb("aimer"),
+            pronoun("je"),
+            pronoun("il"),
+        ]);
+        let sentences = sentences(&words);
+        for sentence in &sentences {
+            assert!(!pairs(sentence).iter().any(|x| [("le", "arbre"), ("je", "aime"), ("ne", "aime"), ("que", "il")].contains(x)), "{}", sentence);
+        }
+        for text in ["l'arbre", "j'aime", "il n'aime pas", "est-ce qu'il"] {
+            assert!(any_contains(&sentences, text), "{}", text);
+        }
+    }
+
+    #[test]
+    fn inverted_questions_put_t_between_vowels() {
+        let words = vocabulary(vec![verb("parler"), verb("finir"), pronoun("il"), pronoun("elle")]);
+        let sentences = sentences(&words);
+        for sentence in &sentences {
+            assert!(!sentence.contains("parle-il") && !sentence.contains("parle-elle") && !sentence.contains("finit-t-"), "{}", sentence);
+        }
+        for text in ["parle-t-il ?", "parle-t-elle ?", "finit-il ?", "ne parle-t-il pas ?"] {
+            assert!(any_contains(&sentences, text), "{}", text);
+        }
+    }
+
+    #[test]
+    fn prepositions_contract_with_the_article() {
+        let words = vocabulary(vec![
+            noun("parc", "parcs", Gender::Male),
+            noun("arbre", "arbres", Gender::Male),
+            noun("maison", "maisons", Gender::Female),
+            article("le", "la", "les", Some("l'")),
+            Category::Preposition("à".to_string()),
+            Category::Preposition("de".to_string()),
+            verb("regarder"),
+            pronoun("il"),
+        ]);
+        let sentences = sentences(&words);
+        for sentence in &sentences {
+            assert!(!pairs(sentence).iter().any(|x| [("à", "le"), ("à", "les"), ("de", "le"), ("de", "les")].contains(x)), "{}", sentence);
+        }
+        for text in ["au parc", "aux parcs", "du parc", "des parcs", "à l'arbre", "de l'arbre", "à la maison", "de la maison"] {
+            assert!(any_contains(&sentences, text), "{}", text);
+        }
+    }
+}
@@ -0,0 +1,178 @@
+use std::{fs, path::{Path, PathBuf}, process::Command, sync::{Arc, Mutex}, thread};
+
+pub const VOICES: [(&str, &str); 4] = [("fr", "French (France)"), ("fr-be", "French (Belgium)"), ("fr-ch", "French (Switzerland)"), ("fr-ca", "French (Canada)")];
+
+// Anything that can turn text into an audio file and play it, so the engine can be swapped out or mocked
+pub trait Speech: Send + Sync {
+    fn synthesize(&self, text: &str, voice: &str, path: &Path) -> Result<(), String>;
+    fn play(&self, path: &Path) -> Result<(), String>;
+}
+
+fn run(command: &mut Command) -> Result<(), String> {
+    match command.status() {
+        Ok(status) if status.success() => Ok(()),
+        Ok(status) => Err(format!("{:?} failed with {}", command.get_program(), status)),
+        Err(err) => Err(format!("Could not run {:?}: {}", command.get_program(), err)),
+    }
+}
+
+pub struct Espeak {
+    pub program: String,
+}
+
+impl Default for Espeak {
+    fn default() -> Self {
+        Self { program: "espeak-ng".to_string() }
+    }
+}
+
+impl Speech for Espeak {
+    fn synthesize(&self, text: &str, voice: &str, path: &Path) -> Result<(), String> {
+        run(Command::new(&self.program).args(["-v", voice, "-w"]).arg(path).arg("--").arg(text))
+    }
+
+    fn play(&self, path: &Path) -> Result<(), String> {
+        if cfg!(target_os = "windows") {
+            // Extra arguments would become part of the script, so the path is passed in the environment where no quote can break it
+            run(Command::new("powershell").args(["-NoProfile", "-c", "(New-Object Media.SoundPlayer $env:FRENCH_AUDIO).PlaySync()"]).env("FRENCH_AUDIO", path))
+        } else if cfg!(target_os = "macos") {
+            run(Command::new("afplay").arg(path))
+        } else {
+            run(Command::new("aplay").arg("-q").arg(path))
+        }
+    }
+}
+
+// The std hasher may change between Rust releases, which would orphan the whole cache
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf29ce484222325, |hash, byte| (hash ^ *byte as u64).wrapping_mul(0x100000001b3))
+}
+
+pub struct Pronouncer {
+    backend: Arc<dyn Speech>,
+    cache: PathBuf,
+    pub voice: String,
+    error: Arc<Mutex<Option<String>>>,
+}
+
+impl Pronouncer {
+    pub fn new(backend: Arc<dyn Speech>, cache: &str) -> Self {
+        Self { backend, cache: PathBuf::from(cache), voice: VOICES[0].0.to_string(), error: Arc::new(Mutex::new(None)) }
+    }
+
+    // Generated audio is kept per voice and text so every word is only synthesized once
+    fn cache_path(&self, text: &str) -> PathBuf {
+        let key = format!("{}\0{}", self.voice, text);
+        self.cache.join(format!("{:016x}.wav", fnv1a(key.as_bytes())))
+    }
+
+    // Runs in the background so the ui doesn't freeze while the engine runs
+    fn background(&self, task: impl FnOnce(&dyn Speech) -> Result<(), String> + Send + 'static) {
+        let (backend, error) = (self.backend.clone(), self.error.clone());
+        *error.lock().unwrap() = None;
+        thread::spawn(move || {
+            if let Err(err) = task(&*backend) {
+                *error.lock().unwrap() = Some(err);
+            }
+        });
+    }
+
+    pub fn speak(&self, text: &str) {
+        let text = text.trim().to_string();
+        if text.is_empty() {
+            return;
+        }
+        let (path, cache, voice) = (self.cache_path(&text), self.cache.clone(), self.voice.clone());
+        self.background(move |backend| {
+            fs::create_dir_all(&cache).map_err(|x| x.to_string())?;
+            if !path.exists() {
+                if let Err(err) = backend.synthesize(&text, &voice, &path) {
+                    // A failed synthesis can leave a broken file behind
+                    let _ = fs::remove_file(&path);
+                    return Err(err);
+                }
+            }
+            backend.play(&path)
+        });
+    }
+
+    // A recording made by the user is preferred over the speech engine
+    pub fn speak_recorded(&self, recording: Option<PathBuf>, text: &str) {
+        match recording {
+            Some(recording) => self.background(move |backend| backend.play(&recording)),
+            None => self.speak(text),
+        }
+    }
+
+    pub fn error(&self) -> Option<String> {
+        self.error.lock().unwrap().clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{sync::mpsc, time::{Duration, Instant}};
+
+    // Writes a file in place of audio and reports every file played
+    struct Fake {
+        fail: bool,
+        synthesized: Mutex<Vec<String>>,
+        played: Mutex<mpsc::Sender<PathBuf>>,
+    }
+
+    impl Speech for Fake {
+        fn synthesize(&self, text: &str, _voice: &str, path: &Path) -> Result<(), String> {
+            self.synthesized.lock().unwrap().push(text.to_string());
+            fs::write(path, text).map_err(|x| x.to_string())?;
+            if self.fail { Err("no voice".to_string()) } else { Ok(()) }
+        }
+
+        fn play(&self, path: &Path) -> Result<(), String> {
+            self.played.lock().unwrap().send(path.to_path_buf()).map_err(|x| x.to_string())
+        }
+    }
+
+    fn pronouncer(name: &str, fail: bool) -> (Pronouncer, Arc<Fake>, mpsc::Receiver<PathBuf>, PathBuf) {
+        let (sender, receiver) = mpsc::channel();
+        let fake = Arc::new(Fake { fail, synthesized: Mutex::new(vec![]), played: Mutex::new(sender) });
+        let cache = std::env::temp_dir().join(format!("french-speech-{}-{}", name, std::process::id()));
+        (Pronouncer::new(fake.clone(), cache.to_str().unwrap()), fake, receiver, cache)
+    }
+
+    #[test]
+    fn synthesizes_once_and_plays_from_cache() {
+        let (pronouncer, fake, played, cache) = pronouncer("cache", false);
+        pronouncer.speak("bonjour");
+        let first = played.recv_timeout(Duration::from_secs(5)).unwrap();
+        pronouncer.speak(" bonjour ");
+        let second = played.recv_timeout(Duration::from_secs(5)).unwrap();
+        assert_eq!(first, second);
+        assert!(first.exists());
+        assert_eq!(*fake.synthesized.lock().unwrap(), vec!["bonjour".to_string()]);
+        fs::remove_dir_all(cache).unwrap();
+    }
+
+    #[test]
+    fn failed_synthesis_leaves_no_file() {
+        let (pronouncer, fake, played, cache) = pronouncer("failure", true);
+        pronouncer.speak("bonjour");
+        let started = Instant::now();
+        while pronouncer.error().is_none() && started.elapsed() < Duration::from_secs(5) {
+            thread::sleep(Duration::from_millis(10));
+        }
+        assert_eq!(pronouncer.error(), Some("no voice".to_string()));
+        assert!(!pronouncer.cache_path("bonjour").exists());
+        assert!(played.try_recv().is_err());
+        assert_eq!(fake.synthesized.lock().unwrap().len(), 1);
+        fs::remove_dir_all(cache).unwrap();
+    }
+
+    #[test]
+    fn cache_names_are_stable() {
+        let (pronouncer, ..) = pronouncer("names", false);
+        assert_eq!(fnv1a(b""), 0xcbf29ce484222325);
+        assert_eq!(fnv1a(b"a"), 0xaf63dc4c8601ec8c);
+        assert_eq!(pronouncer.cache_path("bonjour").file_name().unwrap(), format!("{:016x}.wav", fnv1a(b"fr\0bonjour")).as_str());
+    }
+}
@@ -1,39 +1,148 @@
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
+use std::{collections::HashMap, path::{Path, PathBuf}, time::Duration};
 use eframe::{self, egui::{self, Align, Key, KeyboardShortcut, Modifiers}};
-use levenshtein::levenshtein;
-use crate::{explain::{explain, Part}, practice::{Practice, PracticeGroup, PracticeGroupCollection, Question, QuestionTemplate}, search::{Adjective, Category, Concreteness, Countability, Gender, Item, Language, Noun, NounCategory, Pronoun, ProperOrCommon, Query, Search, VerbForms}, sentence, utils};
+use rand::{seq::SliceRandom, Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use crate::{conjugation::{ConjugationDrill, Tense, VerbFilter, PERSONS, TENSES}, explain::{self, explain, Part}, gender::{self, GenderDrill}, grade::{closeness, grade, grade_sentence, Grade, Token}, history::{Change, History, Target}, media::{self, Media}, merge::{find_duplicates, merge, Duplicates, Merge, Resolution}, phonetics, validate::{validate, Finding}, practice::{Exercise, GroupQuery, GroupSettings, Practice, PracticeGroup, PracticeGroupCollection, Question, QuestionKind, QuestionTemplate, Session, DIRECTIONS, EXERCISES, SPRINT_SECONDS}, speech::{Pronouncer, VOICES}, search::{Adjective, Category, Concreteness, Countability, Gender, Item, Language, LegacyUids, Noun, NounCategory, Pronoun, ProperOrCommon, Query, Search, Uid, VerbForms, parse_alternatives, parse_ipa, parse_tags}, sentence, utils};
 
 #[derive(PartialEq)]
 enum PracticeState {
-    Wrong(String, String, String, usize, Item),
+    Wrong(String, String, String, Grade, Item, Option<SentenceFeedback>, String),
     Question(Question),
     AskContinue,
+    Empty,
+    Summary(Session),
+}
+
+fn template_name(template: &QuestionTemplate, words: &Search, sentences: &Search) -> String {
+    let item = match template {
+        QuestionTemplate::Word(uid) => words.get_item(*uid),
+        QuestionTemplate::Sentence(uid) => sentences.get_item(*uid),
+    };
+    item.and_then(|x| x.language_string(&Language::French).cloned()).unwrap_or("(deleted)".to_string())
+}
+
+// Word by word feedback on a sentence answer, with the words of the french sentence linked to their details
+#[derive(PartialEq)]
+struct SentenceFeedback {
+    tokens: Vec<Token>,
+    words: Vec<(String, Option<Uid>)>,
+}
+
+fn question_state(question: Option<Question>) -> PracticeState {
+    match question {
+        Some(question) => PracticeState::Question(question),
+        None => PracticeState::Empty,
+    }
 }
 
 #[derive(PartialEq)]
 enum Tab {
     Words,
     Sentences,
-    Details(u32),
+    Details(Uid),
     Practice(PracticeState),
     Explain,
     PracticeSelect,
     PracticeView(usize),
-    Example(Vec<(String, Item)>),
+    Example(sentence::Sentence),
+    GenderDrill(GenderDrill),
+    Conjugation(ConjugationDrill),
+    Sessions,
 }
 
 #[derive(PartialEq)]
 enum PopupWindow {
     None,
-    AddWord(String, String, Category, String, Option<u32>),
-    AddSentence(String, String, String, Option<u32>),
-    DeleteWord(u32),
-    DeleteSentence(u32),
-    NewGroup(String, Option<usize>),
+    AddWord(String, String, Category, String, String, String, String, Option<Uid>),
+    AddSentence(String, String, String, Vec<Uid>, String, Option<Uid>),
+    DeleteWord(Uid),
+    DeleteSentence(Uid),
+    NewGroup(String, String, Option<GroupQuery>, Vec<Exercise>, GroupSettings, Option<usize>),
     DeleteGroup(usize),
+    BatchEdit(BatchEdit),
+    DeleteSelection,
+    Export(String),
+    CheckGroups,
+    Merge(MergeState),
+    Duplicates(Vec<Duplicates>),
+    Conjugation(Option<usize>, Vec<Tense>, VerbFilter, bool),
+    AttachMedia(QuestionTemplate, String, Option<String>),
+}
+
+#[derive(PartialEq)]
+enum MergeState {
+    Files(String, String, String, Option<String>),
+    Resolve(Box<Merge>, String, Option<PracticeGroupCollection>),
+}
+
+#[derive(PartialEq, Default)]
+struct BatchEdit {
+    add_tags: String,
+    remove_tags: String,
+    category: Option<Category>,
+    gender: Option<Gender>,
+    countable: Option<Countability>,
+    concrete: Option<Concreteness>,
+    proper: Option<ProperOrCommon>,
+    noun_category: Option<NounCategory>,
+}
+
+impl BatchEdit {
+    fn apply(&self, mut item: Item) -> Item {
+        for tag in parse_tags(&self.add_tags) {
+            if !item.tags.contains(&tag) {
+                item.tags.push(tag);
+            }
+        }
+        let remove_tags = parse_tags(&self.remove_tags);
+        item.tags.retain(|x| !remove_tags.contains(x));
+        // Other categories have forms that would be lost
+        if let (Some(category), Some(french)) = (&self.category, item.category.single_form().cloned()) {
+            item.set_category(match category {
+                Category::Adverb(_) => Category::Adverb(french),
+                Category::Conjunction(_) => Category::Conjunction(french),
+                Category::Interjection(_) => Category::Interjection(french),
+                Category::Preposition(_) => Category::Preposition(french),
+                _ => Category::Other(french),
+            });
+        }
+        if let Category::Noun(noun) = &mut item.category {
+            if let Some(gender) = &self.gender {
+                noun.gender = gender.clone();
+            }
+            if let Some(countable) = &self.countable {
+                noun.countable = countable.clone();
+            }
+            if let Some(concrete) = &self.concrete {
+                noun.concrete = concrete.clone();
+            }
+            if let Some(proper) = &self.proper {
+                noun.proper = proper.clone();
+            }
+            if let Some(noun_category) = &self.noun_category {
+                noun.category = noun_category.clone();
+            }
+        }
+        item
+    }
 }
 
+const CATEGORY_NAMES: [(u16, &str); 11] = [
+    (0b1, "Nouns"),
+    (0b10, "Verbs"),
+    (0b100, "Adjectives"),
+    (0b1000, "Adverbs"),
+    (0b10000, "Articles"),
+    (0b100000, "Conjunctions"),
+    (0b1000000, "Interjections"),
+    (0b10000000, "Prepositions"),
+    (0b100000000, "Pronouns"),
+    (0b1000000000, "Numbers"),
+    (0b10000000000, "Other"),
+];
+
 struct SearchCategories {
     noun: bool,
     verb: bool,
@@ -114,10 +223,33 @@ pub struct App {
     practice_groups_file: String,
     debug: bool,
     reset_scroll: bool,
+    tags: Vec<String>,
+    selected: Vec<Uid>,
+    last_selected: Option<usize>,
+    history: History,
+    show_history: bool,
+    validation: Option<(Target, Vec<Finding>)>,
+    practice_feedback: Option<String>,
+    // An example sentence for the question with the given number
+    practice_hint: Option<(u32, String)>,
+    pronouncer: Pronouncer,
+    media: Media,
+    textures: HashMap<PathBuf, Result<egui::TextureHandle, String>>,
+    // Drills, examples and the seeds of practice sessions all come from this one seedable generator
+    rng: ChaCha8Rng,
+}
+
+// Pictures are decoded once and kept on the gpu until the file is replaced or removed
+fn texture(textures: &mut HashMap<PathBuf, Result<egui::TextureHandle, String>>, ctx: &egui::Context, path: &Path) -> Result<egui::TextureHandle, String> {
+    textures.entry(path.to_path_buf())
+        .or_insert_with(|| media::load_image(path).map(|image| ctx.load_texture(path.display().to_string(), image, Default::default())))
+        .clone()
 }
 
 impl App {
-    pub fn new(_cc: &eframe::CreationContext<'_>, search_words: Search, search_sentences: Search, practice_groups: PracticeGroupCollection, search_words_file: String, search_sentences_file: String, practice_file: String) -> Self {
+    pub fn new(search_words: Search, search_sentences: Search, practice_groups: PracticeGroupCollection, search_words_file: String, search_sentences_file: String, practice_file: String, pronouncer: Pronouncer) -> Self {
+        let media = Media::new(media::MEDIA_DIR);
+        let _ = media.empty_trash();
         let app = Self {
             search_words,
             search_sentences,
@@ -132,17 +264,30 @@ impl App {
             categories: SearchCategories::new(),
             min_num_answers: 0,
             result_explain: vec![],
-            practice: Practice::new(),
+            practice: Practice::new(media.clone()),
             practice_groups,
             practice_groups_file: practice_file,
             debug: false,
             reset_scroll: false,
+            tags: vec![],
+            selected: vec![],
+            last_selected: None,
+            history: History::new(media.clone()),
+            show_history: false,
+            validation: None,
+            practice_feedback: None,
+            practice_hint: None,
+            pronouncer,
+            media,
+            textures: HashMap::new(),
+            rng: ChaCha8Rng::from_entropy(),
         };
         app
     }
 
     fn gen_results(&mut self) {
         self.num_answers = self.min_num_answers;
+        self.last_selected = None;
         match self.tab {
             Tab::Words => {
                 let query = self.gen_query();
@@ -168,23 +313,241 @@ impl App {
     }
 
     fn gen_query(&self) -> Query {
-        Query::new(&self.query_string, &self.language, if self.tab != Tab::Sentences { self.categories.to_u16() } else { !0 }, self.query_string.ends_with('\''))
+        if self.tab != Tab::Sentences {
+            Query::new_with_tags(&self.query_string, &self.language, self.categories.to_u16(), self.query_string.ends_with('\''), &self.tags)
+        } else {
+            Query::new(&self.query_string, &self.language, !0, self.query_string.ends_with('\''))
+        }
+    }
+
+    fn select(&mut self, index: usize, modifiers: Modifiers) {
+        let uid = self.results_search[index].1.uid;
+        if modifiers.shift {
+            let start = self.last_selected.unwrap_or(index);
+            for (_, item) in &self.results_search[start.min(index)..=start.max(index)] {
+                if !self.selected.contains(&item.uid) {
+                    self.selected.push(item.uid);
+                }
+            }
+        } else if modifiers.command {
+            if let Some(position) = self.selected.iter().position(|x| *x == uid) {
+                self.selected.remove(position);
+            } else {
+                self.selected.push(uid);
+            }
+        } else {
+            self.selected = vec![uid];
+        }
+        self.last_selected = Some(index);
+    }
+
+    fn select_all(&mut self) {
+        let query = self.gen_query();
+        let search = if self.tab == Tab::Words { &self.search_words } else { &self.search_sentences };
+        let uids: Vec<Uid> = if query.string.is_empty() {
+            search.all_items(&query).iter().filter(|x| x.language_string(query.language).is_some()).map(|x| x.uid).collect()
+        } else {
+            self.results_search.iter().map(|(_, x)| x.uid).collect()
+        };
+        self.selected = uids;
+    }
+
+    fn selection_menu(&mut self, ui: &mut egui::Ui) {
+        if !self.selected.is_empty() {
+            ui.menu_button(format!("{} selected", self.selected.len()), |ui| {
+                ui.menu_button("Add to practice group", |ui| {
+                    let before = self.practice_groups.groups.clone();
+                    for group in self.practice_groups.groups.iter_mut().filter(|x| x.query.is_none()) {
+                        if ui.button(&group.name).clicked() {
+                            ui.close_menu();
+                            for uid in &self.selected {
+                                let question = if self.tab == Tab::Words { QuestionTemplate::Word(*uid) } else { QuestionTemplate::Sentence(*uid) };
+                                if !group.questions.contains(&question) {
+                                    group.questions.push(question);
+                                }
+                            }
+                            let description = format!("Add {} items to {}", self.selected.len(), group.name);
+                            self.history.record(description, vec![Change::Groups(before.clone(), self.practice_groups.groups.clone())]);
+                            self.practice_groups.save(&self.practice_groups_file);
+                            break;
+                        }
+                    }
+                });
+                if ui.button("Edit").clicked() {
+                    ui.close_menu();
+                    self.popup = PopupWindow::BatchEdit(BatchEdit::default());
+                }
+                if ui.button("Export").clicked() {
+                    ui.close_menu();
+                    self.popup = PopupWindow::Export("export.bin".to_string());
+                }
+                if ui.button("Delete").clicked() {
+                    ui.close_menu();
+                    self.popup = PopupWindow::DeleteSelection;
+                }
+                if ui.button("Clear selection").clicked() {
+                    ui.close_menu();
+                    self.selected.clear();
+                }
+            });
+        }
+        if ui.button("Select all").clicked() {
+            self.select_all();
+        }
+    }
+
+    fn undo(&mut self, redo: bool) {
+        let changed = if redo {
+            self.history.redo(&mut self.search_words, &mut self.search_sentences, &mut self.practice_groups)
+        } else {
+            self.history.undo(&mut self.search_words, &mut self.search_sentences, &mut self.practice_groups)
+        };
+        if changed {
+            self.search_words.save(&self.search_words_file);
+            self.search_sentences.save(&self.search_sentences_file);
+            self.practice_groups.save(&self.practice_groups_file);
+            match self.tab {
+                Tab::Details(uid) if self.search_words.get_item(uid).is_none() => self.tab = Tab::Words,
+                Tab::PracticeView(index) if index >= self.practice_groups.groups.len() => self.tab = Tab::PracticeSelect,
+                _ => {}
+            }
+            self.refresh_validation();
+            self.gen_results();
+        }
+    }
+
+    fn refresh_validation(&mut self) {
+        if let Some((target, findings)) = &mut self.validation {
+            *findings = validate(if *target == Target::Words { &self.search_words } else { &self.search_sentences });
+        }
+    }
+
+    // Clicking a french word selects it, clicking a swedish word then tries to match the selected pair
+    fn on_matching(&mut self, french: bool, index: usize) {
+        let Tab::Practice(PracticeState::Question(question)) = &mut self.tab else {
+            return;
+        };
+        let QuestionKind::Matching(pairs, _, selected) = &mut question.kind else {
+            return;
+        };
+        if french {
+            *selected = Some(index);
+            return;
+        }
+        let Some(chosen) = *selected else {
+            return;
+        };
+        if chosen == index {
+            pairs[index].matched = true;
+            *selected = None;
+        } else {
+            pairs[chosen].mistake = true;
+        }
+        if pairs.iter().all(|x| x.matched) {
+            let results: Vec<(usize, bool)> = pairs.iter().map(|x| (x.index, !x.mistake)).collect();
+            let mut finished = false;
+            for (index, correct) in results {
+                let grade = if correct { Grade::Correct } else { Grade::Wrong };
+                self.practice_groups.record_answer(self.practice.template(index).clone(), grade, correct);
+                finished = self.practice.answer_index(index, correct);
+            }
+            self.practice_groups.save(&self.practice_groups_file);
+            self.tab = if finished {
+                Tab::Practice(PracticeState::AskContinue)
+            } else {
+                Tab::Practice(question_state(self.practice.get_question(&self.search_words, &self.search_sentences)))
+            };
+        }
+    }
+
+    // Saves the session summary once the practice ends or is left for another tab
+    fn check_session(&mut self) {
+        if self.practice.sprint_left().is_some_and(|x| x.is_zero()) && matches!(self.tab, Tab::Practice(PracticeState::Question(_) | PracticeState::Wrong(..))) {
+            self.tab = Tab::Practice(PracticeState::Empty);
+        }
+        if matches!(self.tab, Tab::Practice(PracticeState::Question(_) | PracticeState::Wrong(..) | PracticeState::AskContinue)) {
+            return;
+        }
+        if let Some(session) = self.practice.finish() {
+            self.practice_groups.record_session(session.clone());
+            self.practice_groups.save(&self.practice_groups_file);
+            if self.tab == Tab::Practice(PracticeState::Empty) {
+                self.tab = Tab::Practice(PracticeState::Summary(session));
+            }
+        }
+    }
+
+    fn on_gender(&mut self, gender: Gender) {
+        let Tab::GenderDrill(drill) = &mut self.tab else {
+            return;
+        };
+        let uid = drill.current;
+        let Some(noun) = gender::noun(&self.search_words, uid) else {
+            return;
+        };
+        let correct = drill.answer(&noun, gender, &mut self.rng);
+        self.practice_groups.record_gender(uid, correct, drill.streak);
+        self.practice_groups.save(&self.practice_groups_file);
+    }
+
+    fn on_conjugation(&mut self) {
+        let Tab::Conjugation(drill) = &mut self.tab else {
+            return;
+        };
+        if drill.checked {
+            if !drill.next(&self.search_words, &mut self.rng) {
+                self.tab = Tab::PracticeSelect;
+            }
+            return;
+        }
+        let Some(item) = self.search_words.get_item(drill.current) else {
+            return;
+        };
+        let (grade, correct) = drill.check(&item, &self.practice_groups.leniency);
+        self.practice_groups.record_answer(QuestionTemplate::Word(item.uid), grade, correct);
+        self.practice_groups.save(&self.practice_groups_file);
     }
 
     fn on_enter(&mut self) {
         match &mut self.tab {
             Tab::Practice(ref mut state) => {
                 if let PracticeState::Question(question) = state {
-                    if self.query_string == question.answer {
+                    let search = match self.practice.current_template() {
+                        QuestionTemplate::Word(_) => &self.search_words,
+                        QuestionTemplate::Sentence(_) => &self.search_sentences,
+                    };
+                    let (grade, tokens) = match self.practice.current_template() {
+                        QuestionTemplate::Word(_) if !matches!(question.kind, QuestionKind::Generated(_)) => (grade(&self.query_string, &question.accepted_answers(search), &question.item, &question.language), None),
+                        _ => {
+                            let (grade, tokens) = grade_sentence(&self.query_string, &question.accepted_answers(search), &question.item, &question.language);
+                            (grade, Some(tokens))
+                        }
+                    };
+                    let correct = self.practice_groups.leniency.accepts(grade);
+                    self.practice_groups.record_answer(self.practice.current_template().clone(), grade, correct);
+                    self.practice_groups.save(&self.practice_groups_file);
+                    self.practice_feedback = None;
+                    if correct {
+                        if grade != Grade::Correct {
+                            self.practice_feedback = Some(format!("'{}' was accepted. {} The answer is '{}'.", self.query_string, grade.feedback(), question.answer));
+                        }
                         self.query_string.clear();
                         if self.practice.answer(true) {
                             *state = PracticeState::AskContinue;
                         } else {
-                            *state = PracticeState::Question(self.practice.get_question(&self.search_words, &self.search_sentences));
+                            *state = question_state(self.practice.get_question(&self.search_words, &self.search_sentences));
                         }
                     } else {
                         let _ = self.practice.answer(false);
-                        *state = PracticeState::Wrong(question.string.clone(), question.answer.clone(), self.query_string.clone(), levenshtein(&question.answer, &self.query_string), question.item.clone());
+                        let feedback = tokens.map(|tokens| SentenceFeedback {
+                            tokens,
+                            words: match (&question.kind, &question.item.category) {
+                                (QuestionKind::Generated(words), _) => words.clone(),
+                                (_, Category::Other(sentence)) => explain(sentence, &self.search_words).into_iter().map(|part| (part.string, part.matched.first().map(|x| x.1.uid))).collect(),
+                                _ => vec![],
+                            },
+                        });
+                        *state = PracticeState::Wrong(question.string.clone(), question.answer.clone(), self.query_string.clone(), grade, question.item.clone(), feedback, question.french.clone());
                     }
                 }
             }
@@ -195,11 +558,13 @@ impl App {
 
 impl eframe::App for App {
     fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+        self.check_session();
 
         egui::TopBottomPanel::top("top_panel").show(ctx, |ui| {
             egui::menu::bar(ui, |ui| {
                 if ui.button("Words").clicked() {
                     self.tab = Tab::Words;
+                    self.selected.clear();
                     self.results_search.clear();
                     self.query_string.clear();
                     self.popup = PopupWindow::None;
@@ -207,6 +572,7 @@ impl eframe::App for App {
                 }
                 if ui.button("Sentences").clicked() {
                     self.tab = Tab::Sentences;
+                    self.selected.clear();
                     self.results_search.clear();
                     self.query_string.clear();
                     self.popup = PopupWindow::None;
@@ -223,11 +589,36 @@ impl eframe::App for App {
                     self.popup = PopupWindow::None;
                     self.result_explain.clear();
                 }
+                if ui.selectable_label(self.show_history, "History").clicked() {
+                    self.show_history = !self.show_history;
+                }
+                ui.menu_button("Voice", |ui| {
+                    for (voice, name) in VOICES {
+                        if ui.radio(self.pronouncer.voice == voice, name).clicked() {
+                            self.pronouncer.voice = voice.to_string();
+                        }
+                    }
+                });
+                if let Some(error) = self.pronouncer.error() {
+                    ui.colored_label(ui.visuals().error_fg_color, "Speech failed").on_hover_text(error);
+                }
                 ui.with_layout(egui::Layout::right_to_left(eframe::emath::Align::Center), |ui| {
                     match self.tab {
                         Tab::Words => {
                             if ui.button("Add word").clicked() {
-                                self.popup = PopupWindow::AddWord("".to_string(), "".to_string(), Category::Noun(Noun::default()), "".to_string(), None);
+                                self.popup = PopupWindow::AddWord("".to_string(), "".to_string(), Category::Noun(Noun::default()), "".to_string(), "".to_string(), "".to_string(), "".to_string(), None);
+                            }
+                            if ui.button("Merge").clicked() {
+                                self.popup = PopupWindow::Merge(MergeState::Files("".to_string(), "".to_string(), "".to_string(), None));
+                            }
+                            if ui.button("Duplicates").clicked() {
+                                let search = if self.tab == Tab::Words { &self.search_words } else { &self.search_sentences };
+                                self.popup = PopupWindow::Duplicates(find_duplicates(search));
+                            }
+                            if ui.button("Validate").clicked() {
+                                let target = if self.tab == Tab::Words { Target::Words } else { Target::Sentences };
+                                self.validation = Some((target, vec![]));
+                                self.refresh_validation();
                             }
                             ui.separator();
                             egui::ComboBox::from_id_salt("Language")
@@ -244,10 +635,42 @@ impl eframe::App for App {
                                 }
                             );
                             ui.separator();
+                            self.selection_menu(ui);
                         }
                         Tab::Sentences => {
                             if ui.button("Add sentence").clicked() {
-                                self.popup = PopupWindow::AddSentence("".to_string(), "".to_string(), "".to_string(), None);
+                                self.popup = PopupWindow::AddSentence("".to_string(), "".to_string(), "".to_string(), vec![], "".to_string(), None);
+                            }
+                            if ui.button("Link words").on_hover_text("Link the words of every sentence without linked words").clicked() {
+                                let unlinked: Vec<Item> = self.search_sentences.iter().filter(|x| x.words.is_empty()).cloned().collect();
+                                let mut changes = vec![];
+                                for old in unlinked {
+                                    let Some(french) = old.language_string(&Language::French) else {
+                                        continue;
+                                    };
+                                    let mut item = old.clone();
+                                    item.words = explain::linked_words(french, &self.search_words);
+                                    if !item.words.is_empty() {
+                                        self.search_sentences.edit_item(item.uid, item.clone()).unwrap();
+                                        changes.push(Change::EditItem(Target::Sentences, Box::new(old), Box::new(item)));
+                                    }
+                                }
+                                if !changes.is_empty() {
+                                    self.history.record(format!("Link words in {} sentences", changes.len()), changes);
+                                    self.search_sentences.save(&self.search_sentences_file);
+                                }
+                            }
+                            if ui.button("Merge").clicked() {
+                                self.popup = PopupWindow::Merge(MergeState::Files("".to_string(), "".to_string(), "".to_string(), None));
+                            }
+                            if ui.button("Duplicates").clicked() {
+                                let search = if self.tab == Tab::Words { &self.search_words } else { &self.search_sentences };
+                                self.popup = PopupWindow::Duplicates(find_duplicates(search));
+                            }
+                            if ui.button("Validate").clicked() {
+                                let target = if self.tab == Tab::Words { Target::Words } else { Target::Sentences };
+                                self.validation = Some((target, vec![]));
+                                self.refresh_validation();
                             }
                             ui.separator();
                             egui::ComboBox::from_id_salt("Language")
@@ -264,8 +687,9 @@ impl eframe::App for App {
                                 }
                             );
                             ui.separator();
+                            self.selection_menu(ui);
                         }
-                        Tab::Details(_) | Tab::Practice(_) | Tab::Example(_) | Tab::PracticeView(_) => {
+                        Tab::Details(_) | Tab::Practice(_) | Tab::Example(_) | Tab::PracticeView(_) | Tab::GenderDrill(_) | Tab::Conjugation(_) | Tab::Sessions => {
 
                         }
                         Tab::Explain => {
@@ -283,8 +707,39 @@ impl eframe::App for App {
                             // ui.separator();
                         }
                         Tab::PracticeSelect => {
+                            ui.menu_button("Leniency", |ui| {
+                                let leniency = &mut self.practice_groups.leniency;
+                                if ui.checkbox(&mut leniency.case, "Accept capitalization and spacing mistakes").changed() |
+                                ui.checkbox(&mut leniency.accent, "Accept accent mistakes").changed() |
+                                ui.checkbox(&mut leniency.elision, "Accept missing elision").changed() {
+                                    self.practice_groups.save(&self.practice_groups_file);
+                                }
+                            });
+                            if ui.button("Gender drill").clicked() {
+                                let uids = self.search_words.iter().filter(|x| gender::drillable(x)).map(|x| x.uid).collect();
+                                if let Some(drill) = GenderDrill::new(uids, &mut self.rng) {
+                                    self.tab = Tab::GenderDrill(drill);
+                                }
+                            }
+                            if ui.button("Conjugation drill").clicked() {
+                                self.popup = PopupWindow::Conjugation(None, TENSES.to_vec(), VerbFilter::All, false);
+                            }
+                            ui.menu_button("Daily goal", |ui| {
+                                ui.horizontal(|ui| {
+                                    if ui.add(egui::DragValue::new(&mut self.practice_groups.daily_goal).range(0..=1000)).changed() {
+                                        self.practice_groups.save(&self.practice_groups_file);
+                                    }
+                                    ui.label("questions per day");
+                                });
+                            });
+                            if ui.button("Sessions").clicked() {
+                                self.tab = Tab::Sessions;
+                            }
+                            if ui.button("Check groups").clicked() {
+                                self.popup = PopupWindow::CheckGroups;
+                            }
                             if ui.button("New group").clicked() {
-                                self.popup = PopupWindow::NewGroup("".to_string(), None);
+                                self.popup = PopupWindow::NewGroup("".to_string(), "".to_string(), None, vec![Exercise::Typed], GroupSettings::default(), None);
                             }
                         }
                     }
@@ -311,6 +766,29 @@ impl eframe::App for App {
                         self.reset_scroll = true;
                         self.gen_results();
                     }
+
+                    let all_tags = self.search_words.all_tags();
+                    if !all_tags.is_empty() {
+                        ui.separator();
+                        ui.heading("Tags");
+
+                        let mut changed = false;
+                        for tag in all_tags {
+                            let mut selected = self.tags.contains(&tag);
+                            if ui.checkbox(&mut selected, &tag).changed() {
+                                if selected {
+                                    self.tags.push(tag);
+                                } else {
+                                    self.tags.retain(|x| *x != tag);
+                                }
+                                changed = true;
+                            }
+                        }
+                        if changed {
+                            self.reset_scroll = true;
+                            self.gen_results();
+                        }
+                    }
                 });
             }
             _ => {}
@@ -319,29 +797,99 @@ impl eframe::App for App {
         if ctx.input_mut(|state| state.consume_shortcut(&KeyboardShortcut::new(Modifiers::CTRL, Key::D))) {
             self.debug = !self.debug;
         }
+        if ctx.input_mut(|state| state.consume_shortcut(&KeyboardShortcut::new(Modifiers::CTRL | Modifiers::SHIFT, Key::Z))) {
+            self.undo(true);
+        } else if ctx.input_mut(|state| state.consume_shortcut(&KeyboardShortcut::new(Modifiers::CTRL, Key::Z))) {
+            self.undo(false);
+        }
+        if let Tab::GenderDrill(drill) = &mut self.tab {
+            let uid = drill.current;
+            // The noun was deleted or edited into something else during the drill
+            if gender::noun(&self.search_words, uid).is_none() && !drill.remove(uid, &mut self.rng) {
+                self.tab = Tab::PracticeSelect;
+            }
+        }
+        if let Tab::Conjugation(drill) = &mut self.tab {
+            let verb = matches!(self.search_words.get_item(drill.current), Some(Item { category: Category::Verb(..), .. }));
+            if !verb && !drill.next(&self.search_words, &mut self.rng) {
+                self.tab = Tab::PracticeSelect;
+            }
+        }
+        if self.show_history {
+            egui::SidePanel::right("history_panel").resizable(false).show(ctx, |ui| {
+                ui.heading("History");
+
+                ui.horizontal(|ui| {
+                    if ui.add_enabled(!self.history.undo_edits().is_empty(), egui::Button::new("Undo")).clicked() {
+                        self.undo(false);
+                    }
+                    if ui.add_enabled(!self.history.redo_edits().is_empty(), egui::Button::new("Redo")).clicked() {
+                        self.undo(true);
+                    }
+                });
+                ui.separator();
+                egui::ScrollArea::vertical().show(ui, |ui| {
+                    for edit in self.history.redo_edits() {
+                        ui.weak(&edit.description);
+                    }
+                    for edit in self.history.undo_edits().iter().rev() {
+                        ui.label(&edit.description);
+                    }
+                });
+            });
+        }
+        if let Some((target, findings)) = &self.validation {
+            let mut hide = false;
+            egui::SidePanel::right("validation_panel").resizable(false).show(ctx, |ui| {
+                ui.heading("Validation");
+
+                ui.horizontal(|ui| {
+                    ui.label(format!("{} problems", findings.len()));
+                    if ui.button("Close").clicked() {
+                        hide = true;
+                    }
+                });
+                ui.separator();
+                egui::ScrollArea::vertical().show(ui, |ui| {
+                    for finding in findings {
+                        let search = if *target == Target::Words { &self.search_words } else { &self.search_sentences };
+                        let Some(item) = search.get_item(finding.uid) else {
+                            continue;
+                        };
+                        ui.horizontal(|ui| {
+                            if ui.link(&finding.french).clicked() {
+                                self.popup = match target {
+                                    Target::Words => PopupWindow::AddWord(item.swedish.clone().unwrap_or_default(), item.english.clone().unwrap_or_default(), item.category.clone(), item.tags.join(", "), "".to_string(), item.alternatives.join(", "), item.ipa.clone().unwrap_or_default(), Some(item.uid)),
+                                    Target::Sentences => PopupWindow::AddSentence(match &item.category {
+                                        Category::Other(string) => string.clone(),
+                                        _ => "".to_string(),
+                                    }, item.swedish.clone().unwrap_or_default(), item.english.clone().unwrap_or_default(), item.words.clone(), "".to_string(), Some(item.uid)),
+                                };
+                            }
+                            ui.label(finding.issue.to_string());
+                        });
+                    }
+                });
+            });
+            if hide {
+                self.validation = None;
+            }
+        }
         if self.debug {
             egui::SidePanel::right("debug_panel").resizable(false).show(ctx, |ui| {
                 ui.heading("Debug");
 
                 ui.label("Frame time:");
                 ui.label(format!("{:.1} ms", ctx.input(|state| state.stable_dt) * 1000.0));    
-
-                if ui.button("Fix uids").clicked() {
-                    let indices = self.practice_groups.get_group_indices(&self.search_words, &self.search_sentences);
-                    self.search_words.recreate_uids();
-                    self.search_sentences.recreate_uids();
-                    self.practice_groups.update_group_indices(&self.search_words, &self.search_sentences, indices);
-                    self.search_words.save(&self.search_words_file);
-                    self.search_sentences.save(&self.search_sentences_file);
-                    self.practice_groups.save(&self.practice_groups_file);
-                }
             });
         }
 
         let mut change_tab: Option<Tab> = None;
+        let mut matching_click = None;
+        let mut timed_out = false;
         egui::CentralPanel::default().show(ctx, |ui| {
             let width = ui.available_width();
-            match &self.tab {
+            match &mut self.tab {
                 Tab::Details(uid) => {
                     let item = self.search_words.get_item(*uid).unwrap();
                     egui::Grid::new("verb_grid")
@@ -357,12 +905,23 @@ impl eframe::App for App {
                                 ui.end_row();
                                 translation = true;
                             }
+                            if !item.alternatives.is_empty() {
+                                ui.label("Other Swedish translations");
+                                ui.label(item.alternatives.join(", "));
+                                ui.end_row();
+                            }
                             if let Some(string) = &item.english {
                                 ui.label("English");
                                 ui.label(string);
                                 ui.end_row();
                                 translation = true;
                             }
+                            if let Some(ipa) = &item.ipa {
+                                ui.label("Pronunciation");
+                                ui.label(format!("/{}/", ipa));
+                                ui.end_row();
+                                translation = true;
+                            }
                             if translation {
                                 ui.end_row();
                             }
@@ -590,7 +1149,7 @@ impl eframe::App for App {
                                     if let Some(fraction) = fraction {
                                         ui.end_row();
                                         ui.label("Fraction");
-                                        ui.label(&format!("1/{}", item.swedish.clone().unwrap()));
+                                        ui.label(&format!("1/{}", item.swedish.clone().unwrap_or_default()));
                                         ui.end_row();
                                         ui.label("Fraction");
                                         ui.label(fraction);
@@ -707,11 +1266,53 @@ impl eframe::App for App {
                                     }
                                 }
                             }
+                            if !item.tags.is_empty() {
+                                ui.end_row();
+                                ui.label("Tags");
+                                ui.label(item.tags.join(", "));
+                                ui.end_row();
+                            }
                             if self.debug {
                                 ui.label("Uid");
                                 ui.label(format!("{}", item.uid));
                             }
                         });
+                    let name = QuestionTemplate::Word(item.uid).media_name();
+                    if let Some(recording) = self.media.audio(&name) {
+                        if ui.button("▶ Play recording").clicked() {
+                            self.pronouncer.speak_recorded(Some(recording), "");
+                        }
+                    }
+                    let sentences = self.search_sentences.linking(item.uid);
+                    if !sentences.is_empty() {
+                        ui.separator();
+                        ui.label("Examples");
+                        let forms = explain::forms(&item);
+                        for sentence in sentences {
+                            let Some(french) = sentence.language_string(&Language::French) else {
+                                continue;
+                            };
+                            ui.horizontal_wrapped(|ui| {
+                                ui.spacing_mut().item_spacing.x = 0.;
+                                for (part, marked) in explain::mark_words(french, &forms) {
+                                    if marked {
+                                        ui.label(egui::RichText::new(part).strong().color(ui.visuals().hyperlink_color));
+                                    } else {
+                                        ui.label(part);
+                                    }
+                                }
+                                if let Some(swedish) = &sentence.swedish {
+                                    ui.label(egui::RichText::new(format!("  {}", swedish)).weak());
+                                }
+                            });
+                        }
+                    }
+                    if let Some(path) = self.media.image(&name) {
+                        match texture(&mut self.textures, ui.ctx(), &path) {
+                            Ok(texture) => { ui.add(egui::Image::new(&texture).max_size(egui::vec2(width, ui.available_height()))); }
+                            Err(err) => { ui.label(format!("Could not load the picture: {}", err)); }
+                        }
+                    }
                 }
                 Tab::Words |
                 Tab::Sentences |
@@ -734,9 +1335,13 @@ impl eframe::App for App {
                                             ui.scroll_to_rect(ui.cursor(), Some(Align::TOP));
                                             self.reset_scroll = false;
                                         }
+                                        let mut clicked = None;
                                         for i in row_range.start..row_range.end.min(self.results_search.len()) {
                                             let (string, item) = &self.results_search[i];
-                                            let response = ui.label(string);
+                                            let response = ui.selectable_label(self.selected.contains(&item.uid), string);
+                                            if response.clicked() {
+                                                clicked = Some(i);
+                                            }
                                             response.clone().on_hover_ui_at_pointer(|ui| {
                                                 ui.label(format!("{}", item.tooltip()));
                                                 if self.debug {
@@ -749,18 +1354,29 @@ impl eframe::App for App {
                                                     self.tab = Tab::Details(item.uid);
                                                     self.popup = PopupWindow::None;
                                                 }
+                                                if let Some(french) = item.language_string(&Language::French) {
+                                                    if ui.button("Pronounce").clicked() {
+                                                        ui.close_menu();
+                                                        self.pronouncer.speak_recorded(self.media.audio(&QuestionTemplate::Word(item.uid).media_name()), french);
+                                                    }
+                                                }
                                                 if let Category::Verb(..) = item.category {
                                                     if ui.button("Example").clicked() {
                                                         ui.close_menu();
-                                                        self.tab = Tab::Example(sentence::generate(&self.search_words, None, Some(item.clone())));
-                                                        self.popup = PopupWindow::None;
+                                                        if let Some(example) = sentence::generate(&self.search_words, None, Some(item.clone()), None, &mut self.rng) {
+                                                            self.tab = Tab::Example(example);
+                                                            self.popup = PopupWindow::None;
+                                                        }
                                                     }
                                                 }
                                                 ui.menu_button("Add to practice group", |ui| {
-                                                    for group in &mut self.practice_groups.groups {
+                                                    let before = self.practice_groups.groups.clone();
+                                                    for group in self.practice_groups.groups.iter_mut().filter(|x| x.query.is_none()) {
                                                         if ui.button(&group.name).clicked() {
                                                             ui.close_menu();
                                                             group.questions.push(QuestionTemplate::Word(item.uid));
+                                                            let description = format!("Add '{}' to {}", string, group.name);
+                                                            self.history.record(description, vec![Change::Groups(before.clone(), self.practice_groups.groups.clone())]);
                                                             self.practice_groups.save(&self.practice_groups_file);
                                                             break;
                                                         }
@@ -774,7 +1390,7 @@ impl eframe::App for App {
                                                     }, match item.english.clone() {
                                                         None => "".to_string(),
                                                         Some(val) => val,
-                                                    }, item.category.clone(), "".to_string(), Some(item.uid));
+                                                    }, item.category.clone(), item.tags.join(", "), "".to_string(), item.alternatives.join(", "), item.ipa.clone().unwrap_or_default(), Some(item.uid));
                                                 }
                                                 if ui.button("Delete").clicked() {
                                                     ui.close_menu();
@@ -782,6 +1398,9 @@ impl eframe::App for App {
                                                 }
                                             });
                                         }
+                                        if let Some(i) = clicked {
+                                            self.select(i, ui.input(|state| state.modifiers));
+                                        }
                                         if row_range.end == self.results_search.len() + 1 {
                                             if ui.add_enabled(self.results_search.len() == self.num_answers, egui::Button::new("Load more")).clicked() {
                                                 self.min_num_answers += 100;
@@ -805,9 +1424,13 @@ impl eframe::App for App {
                                             self.reset_scroll = false;
                                         }
                                         let mut update_results = false;
+                                        let mut clicked = None;
                                         for i in row_range.start..row_range.end.min(self.results_search.len()) {
                                             let (string, item) = &self.results_search[i];
-                                            let response = ui.label(string);
+                                            let response = ui.selectable_label(self.selected.contains(&item.uid), string);
+                                            if response.clicked() {
+                                                clicked = Some(i);
+                                            }
                                             response.clone().on_hover_ui_at_pointer(|ui| {
                                                 ui.label(format!("{}", item.tooltip()));
                                                 if self.debug {
@@ -822,10 +1445,13 @@ impl eframe::App for App {
                                                     update_results = true;
                                                 }
                                                 ui.menu_button("Add to practice group", |ui| {
-                                                    for group in &mut self.practice_groups.groups {
+                                                    let before = self.practice_groups.groups.clone();
+                                                    for group in self.practice_groups.groups.iter_mut().filter(|x| x.query.is_none()) {
                                                         if ui.button(&group.name).clicked() {
                                                             ui.close_menu();
                                                             group.questions.push(QuestionTemplate::Sentence(item.uid));
+                                                            let description = format!("Add '{}' to {}", string, group.name);
+                                                            self.history.record(description, vec![Change::Groups(before.clone(), self.practice_groups.groups.clone())]);
                                                             self.practice_groups.save(&self.practice_groups_file);
                                                             break;
                                                         }
@@ -842,7 +1468,20 @@ impl eframe::App for App {
                                                     }, match item.english.clone() {
                                                         None => "".to_string(),
                                                         Some(val) => val,
-                                                    }, Some(item.uid));
+                                                    }, item.words.clone(), "".to_string(), Some(item.uid));
+                                                }
+                                                // Dictation plays the recording of a sentence instead of the synthesized voice
+                                                if ui.button("Attach recording").clicked() {
+                                                    ui.close_menu();
+                                                    self.popup = PopupWindow::AttachMedia(QuestionTemplate::Sentence(item.uid), "".to_string(), None);
+                                                }
+                                                if let Some(path) = self.media.audio(&QuestionTemplate::Sentence(item.uid).media_name()) {
+                                                    if ui.button("Remove recording").clicked() {
+                                                        ui.close_menu();
+                                                        if let Err(err) = self.media.remove(&path) {
+                                                            self.popup = PopupWindow::AttachMedia(QuestionTemplate::Sentence(item.uid), "".to_string(), Some(err));
+                                                        }
+                                                    }
                                                 }
                                                 if ui.button("Delete").clicked() {
                                                     ui.close_menu();
@@ -853,6 +1492,9 @@ impl eframe::App for App {
                                         if update_results {
                                             self.gen_results();
                                         }
+                                        if let Some(i) = clicked {
+                                            self.select(i, ui.input(|state| state.modifiers));
+                                        }
                                         if row_range.end == self.results_search.len() + 1 {
                                             if ui.add_enabled(self.results_search.len() == self.num_answers, egui::Button::new("Load more")).clicked() {
                                                 self.min_num_answers += 100;
@@ -861,27 +1503,65 @@ impl eframe::App for App {
                                     });
                             }
                             Tab::PracticeSelect => {
+                                let answered = self.practice_groups.answered_today();
+                                let streak = self.practice_groups.current_streak();
+                                match self.practice_groups.daily_goal {
+                                    0 => ui.label(format!("{} questions answered today, {} day streak", answered, streak)),
+                                    goal => ui.label(format!("{} of {} questions answered today, {} day streak", answered.min(goal), goal, streak)),
+                                };
                                 for (i, group) in self.practice_groups.groups.iter().enumerate() {
                                     let response = ui.button(&group.name);
+                                    let mut sprint = false;
+                                    let mut generated = false;
                                     response.clone().context_menu(|ui| {
                                         if ui.button("View").clicked() {
                                             ui.close_menu();
                                             self.tab = Tab::PracticeView(i);
                                         }
+                                        if ui.button(format!("Sprint ({} minutes)", SPRINT_SECONDS / 60)).clicked() {
+                                            ui.close_menu();
+                                            sprint = true;
+                                        }
+                                        if ui.button("Generated sentences").clicked() {
+                                            ui.close_menu();
+                                            generated = true;
+                                        }
+                                        if ui.button("Gender drill").clicked() {
+                                            ui.close_menu();
+                                            let uids = self.practice_groups.get_questions(i, &self.search_words, &self.search_sentences).iter().filter_map(|x| match x {
+                                                QuestionTemplate::Word(uid) => self.search_words.get_item(*uid).filter(gender::drillable).map(|x| x.uid),
+                                                QuestionTemplate::Sentence(_) => None,
+                                            }).collect();
+                                            if let Some(drill) = GenderDrill::new(uids, &mut self.rng) {
+                                                self.tab = Tab::GenderDrill(drill);
+                                            }
+                                        }
+                                        if ui.button("Conjugation drill").clicked() {
+                                            ui.close_menu();
+                                            self.popup = PopupWindow::Conjugation(Some(i), TENSES.to_vec(), VerbFilter::All, false);
+                                        }
                                         if ui.button("Edit").clicked() {
                                             ui.close_menu();
-                                            self.popup = PopupWindow::NewGroup(group.name.clone(), Some(i));
+                                            self.popup = PopupWindow::NewGroup(group.name.clone(), group.query.as_ref().map_or("".to_string(), |x| x.tags.join(", ")), group.query.clone(), group.exercises.clone(), group.settings.clone(), Some(i));
                                         }
                                         if ui.button("Delete").clicked() {
                                             ui.close_menu();
                                             self.popup = PopupWindow::DeleteGroup(i);
                                         }
                                     });
-                                    if response.clicked() {
-                                        self.practice.init(&self.practice_groups.groups[i]);
-                                        self.tab = Tab::Practice(PracticeState::Question(self.practice.get_question(&self.search_words, &self.search_sentences)));
-                                        self.query_string.clear();
-                                        self.popup = PopupWindow::None;
+                                    if response.clicked() || sprint || generated {
+                                        let questions = self.practice_groups.get_questions(i, &self.search_words, &self.search_sentences);
+                                        if !questions.is_empty() {
+                                            let exercises = if generated { vec![Exercise::Generated] } else { group.exercises.clone() };
+                                            self.practice.init(group.name.clone(), questions, exercises, group.settings.clone(), self.rng.gen());
+                                            if sprint {
+                                                self.practice.start_sprint(Duration::from_secs(SPRINT_SECONDS));
+                                            }
+                                            self.practice_feedback = None;
+                                            self.tab = Tab::Practice(question_state(self.practice.get_question(&self.search_words, &self.search_sentences)));
+                                            self.query_string.clear();
+                                            self.popup = PopupWindow::None;
+                                        }
                                     }
                                 }
                             }
@@ -893,29 +1573,272 @@ impl eframe::App for App {
                     ui.with_layout(egui::Layout::top_down(egui::Align::Center), |ui| {
                         match state {
                             PracticeState::Question(question) => {
-                                ui.heading(&question.string);
+                                if let QuestionKind::Dictation(spoken) = &mut question.kind {
+                                    let recording = self.media.audio(&self.practice.current_template().media_name());
+                                    if !*spoken {
+                                        *spoken = true;
+                                        self.pronouncer.speak_recorded(recording.clone(), &question.french);
+                                    }
+                                    ui.horizontal(|ui| {
+                                        ui.heading(&question.string);
+                                        if ui.button("🔊").on_hover_text("Listen again").clicked() {
+                                            self.pronouncer.speak_recorded(recording.clone(), &question.french);
+                                        }
+                                    });
+                                } else if question.language != Language::French && !matches!(question.kind, QuestionKind::Matching(..)) {
+                                    // Only offered when the french is in the question, pronouncing a french answer would give it away
+                                    ui.horizontal(|ui| {
+                                        ui.heading(&question.string);
+                                        if ui.button("🔊").on_hover_text("Pronounce").clicked() {
+                                            self.pronouncer.speak(&question.french);
+                                        }
+                                    });
+                                } else {
+                                    ui.heading(&question.string);
+                                }
+                                if let QuestionKind::Picture(path) = &question.kind {
+                                    match texture(&mut self.textures, ui.ctx(), path) {
+                                        Ok(texture) => { ui.add(egui::Image::new(&texture).max_size(egui::vec2(width, 300.))); }
+                                        Err(err) => { ui.label(format!("Could not load the picture: {}", err)); }
+                                    }
+                                }
+                                if let Some(feedback) = &self.practice_feedback {
+                                    ui.label(feedback);
+                                }
+                                if let (QuestionTemplate::Word(uid), QuestionKind::Typed | QuestionKind::Choice(_) | QuestionKind::Picture(_)) = (self.practice.current_template(), &question.kind) {
+                                    match &self.practice_hint {
+                                        Some((asked, hint)) if *asked == self.practice.asked() => {
+                                            ui.label(hint);
+                                        }
+                                        _ => {
+                                            let examples = self.search_sentences.linking(*uid);
+                                            if !examples.is_empty() && ui.button("Hint").on_hover_text("Show an example sentence").clicked() {
+                                                if let Some(example) = examples.choose(&mut self.rng) {
+                                                    let french = example.language_string(&Language::French).cloned().unwrap_or_default();
+                                                    // The word itself would give a french answer away
+                                                    let hint = if question.language == Language::French {
+                                                        explain::mark_words(&french, &explain::forms(&question.item)).into_iter().map(|(part, marked)| if marked { "___".to_string() } else { part }).collect()
+                                                    } else {
+                                                        french
+                                                    };
+                                                    self.practice_hint = Some((self.practice.asked(), hint));
+                                                }
+                                            }
+                                        }
+                                    }
+                                }
+                                if let Some(left) = self.practice.sprint_left() {
+                                    ui.label(format!("Sprint: {} left", utils::duration(left.as_secs_f32().ceil() as u64)));
+                                    ui.ctx().request_repaint_after(Duration::from_millis(100));
+                                }
+                                if let (Some(left), false) = (self.practice.time_left(), matches!(question.kind, QuestionKind::Matching(..))) {
+                                    ui.label(format!("{} seconds left", left.as_secs_f32().ceil()));
+                                    // Running out of time submits whatever has been typed so far
+                                    if left.is_zero() {
+                                        timed_out = true;
+                                    } else {
+                                        ui.ctx().request_repaint_after(Duration::from_millis(100));
+                                    }
+                                }
+                                if let QuestionKind::Matching(pairs, order, selected) = &question.kind {
+                                    egui::Grid::new("matching_grid")
+                                        .num_columns(2)
+                                        .spacing([40.0, 8.0])
+                                        .show(ui, |ui| {
+                                            for (row, right) in order.iter().enumerate() {
+                                                if ui.add_enabled(!pairs[row].matched, egui::SelectableLabel::new(*selected == Some(row), &pairs[row].french)).clicked() {
+                                                    matching_click = Some((true, row));
+                                                }
+                                                if ui.add_enabled(!pairs[*right].matched, egui::Button::new(&pairs[*right].swedish)).clicked() {
+                                                    matching_click = Some((false, *right));
+                                                }
+                                                ui.end_row();
+                                            }
+                                        });
+                                }
                             }
-                            PracticeState::Wrong(question, correct, answer, difference, _) => {
-                                ui.heading(question);
+                            PracticeState::Wrong(question, correct, answer, grade, _, feedback, french) => {
+                                ui.horizontal(|ui| {
+                                    ui.heading(question);
+                                    if ui.button("🔊").on_hover_text("Pronounce").clicked() {
+                                        self.pronouncer.speak(french);
+                                    }
+                                });
                                 ui.label(format!("The correct answer was '{}', not '{}'.", correct, answer));
-                                if difference <= &2 {
-                                    ui.label("You were close.");
-                                } else if difference <= &3 {
-                                    ui.label("That's almost close.");
+                                if *grade == Grade::Wrong {
+                                    ui.label(closeness(answer, correct));
                                 } else {
-                                    ui.label("You need to practice this more.");
+                                    ui.label(grade.feedback());
+                                }
+                                if let Some(feedback) = feedback {
+                                    ui.add_space(10.0);
+                                    ui.horizontal_wrapped(|ui| {
+                                        for token in &feedback.tokens {
+                                            match token {
+                                                Token::Correct(word) => {
+                                                    ui.label(word);
+                                                }
+                                                Token::Misspelled(answer, expected) => {
+                                                    ui.label(egui::RichText::new(format!("{} → {}", answer, expected)).color(ui.visuals().warn_fg_color)).on_hover_text("Misspelled");
+                                                }
+                                                Token::Missing(expected) => {
+                                                    ui.label(egui::RichText::new(expected).underline().color(ui.visuals().error_fg_color)).on_hover_text("Missing");
+                                                }
+                                                Token::Extra(answer) => {
+                                                    ui.label(egui::RichText::new(answer).strikethrough().color(ui.visuals().error_fg_color)).on_hover_text("Extra");
+                                                }
+                                            }
+                                        }
+                                    });
+                                    ui.add_space(10.0);
+                                    ui.horizontal_wrapped(|ui| {
+                                        for (word, uid) in &feedback.words {
+                                            match uid {
+                                                Some(uid) => {
+                                                    if ui.link(word).clicked() {
+                                                        change_tab = Some(Tab::Details(*uid));
+                                                    }
+                                                }
+                                                None => {
+                                                    ui.label(word).on_hover_text("Not in the word list");
+                                                }
+                                            }
+                                        }
+                                    });
                                 }
                             }
                             PracticeState::AskContinue => {
                                 ui.heading("You know all of the words, continue anyway?");
                             }
-                        }
-                    });
-                }
-                Tab::Example(words) => {
-                    ui.with_layout(egui::Layout::left_to_right(egui::Align::TOP), |ui| {
-                        for (word, item) in words {
-                            let response = ui.label(word);
+                            PracticeState::Empty => {
+                                if self.practice.session_over() {
+                                    ui.heading(format!("The session is over, you answered {} questions.", self.practice.asked()));
+                                } else {
+                                    ui.heading("There are no words left to practice in this group.");
+                                }
+                            }
+                            PracticeState::Summary(session) => {
+                                ui.heading(if session.sprint { "Sprint summary" } else { "Session summary" });
+                                ui.label(&session.group);
+                                ui.label(format!("Time spent: {}", utils::duration(session.seconds)));
+                                ui.label(format!("{} of {} correct ({}%)", session.correct, session.total, session.percent()));
+                                if !session.hardest.is_empty() {
+                                    ui.add_space(10.0);
+                                    ui.label("Hardest items");
+                                    for (template, mistakes) in &session.hardest {
+                                        ui.label(format!("{} ({} mistakes)", template_name(template, &self.search_words, &self.search_sentences), mistakes));
+                                    }
+                                }
+                            }
+                        }
+                    });
+                }
+                Tab::GenderDrill(drill) => {
+                    ui.with_layout(egui::Layout::top_down(egui::Align::Center), |ui| {
+                        ui.label(format!("Streak: {} (best {})", drill.streak, self.practice_groups.best_gender_streak));
+                        ui.label(format!("{} of {} correct", drill.correct, drill.answered));
+                        if let Some((noun, correct, rule)) = &drill.feedback {
+                            if *correct {
+                                ui.label(format!("Right, {}.", noun));
+                            } else {
+                                ui.colored_label(ui.visuals().error_fg_color, format!("Wrong, it is {}.", noun));
+                            }
+                            if let Some(rule) = rule {
+                                ui.label(rule);
+                            }
+                        }
+                        ui.add_space(20.0);
+                        if let Some(noun) = gender::noun(&self.search_words, drill.current) {
+                            let (male, female) = drill.articles(&noun);
+                            ui.heading(format!("{} or {} {}?", male, female, noun.singular));
+                            if let Some(accuracy) = self.practice_groups.gender_accuracy.get(&drill.current) {
+                                ui.label(format!("Right {} of {} times", accuracy.correct, accuracy.total));
+                            }
+                        }
+                    });
+                }
+                Tab::Conjugation(drill) => {
+                    ui.with_layout(egui::Layout::top_down(egui::Align::Center), |ui| {
+                        ui.label(format!("{} of {} tables correct", drill.correct, drill.answered));
+                        ui.heading(format!("{} ({})", drill.infinitive, drill.tense));
+                        ui.add_space(20.0);
+                        let checked = drill.checked;
+                        egui::Grid::new("conjugation_grid")
+                            .num_columns(3)
+                            .spacing([20.0, 8.0])
+                            .show(ui, |ui| {
+                                for cell in &mut drill.cells {
+                                    if cell.irregular {
+                                        ui.colored_label(ui.visuals().warn_fg_color, &cell.person).on_hover_text("Irregular form");
+                                    } else {
+                                        ui.label(&cell.person);
+                                    }
+                                    ui.add_enabled(!checked, egui::TextEdit::singleline(&mut cell.answer));
+                                    match cell.grade {
+                                        Some(Grade::Correct) => {
+                                            ui.label("✔");
+                                        }
+                                        Some(grade) => {
+                                            let text = format!("{} {}", cell.expected, grade.feedback());
+                                            if self.practice_groups.leniency.accepts(grade) {
+                                                ui.label(text);
+                                            } else {
+                                                ui.colored_label(ui.visuals().error_fg_color, text);
+                                            }
+                                        }
+                                        None => {
+                                            ui.label("");
+                                        }
+                                    }
+                                    ui.end_row();
+                                }
+                            });
+                    });
+                }
+                Tab::Sessions => {
+                    let mut replay = None;
+                    egui::ScrollArea::vertical().show(ui, |ui| {
+                        if self.practice_groups.sessions.is_empty() {
+                            ui.label("No practice sessions yet.");
+                        }
+                        egui::Grid::new("sessions_grid")
+                            .num_columns(7)
+                            .striped(true)
+                            .spacing([20.0, 4.0])
+                            .show(ui, |ui| {
+                                for session in self.practice_groups.sessions.iter().rev() {
+                                    ui.label(utils::date(session.started));
+                                    ui.label(if session.sprint { format!("{} (sprint)", session.group) } else { session.group.clone() });
+                                    ui.label(utils::duration(session.seconds));
+                                    ui.label(format!("{} of {}", session.correct, session.total));
+                                    ui.label(format!("{}%", session.percent()));
+                                    ui.label(session.hardest.iter().map(|(template, _)| template_name(template, &self.search_words, &self.search_sentences)).collect::<Vec<String>>().join(", "));
+                                    // The session is asked again from what it was started with, which needs all of its items to still exist
+                                    if session.replay.questions.iter().all(|q| q.exists(&self.search_words, &self.search_sentences)) && ui.button("Replay").clicked() {
+                                        replay = Some((session.group.clone(), session.sprint, session.replay.clone()));
+                                    }
+                                    ui.end_row();
+                                }
+                            });
+                    });
+                    if let Some((name, sprint, replay)) = replay {
+                        if !replay.questions.is_empty() {
+                            self.practice.init(name, replay.questions, replay.exercises, replay.settings, replay.seed);
+                            if sprint {
+                                self.practice.start_sprint(Duration::from_secs(SPRINT_SECONDS));
+                            }
+                            self.practice_feedback = None;
+                            change_tab = Some(Tab::Practice(question_state(self.practice.get_question(&self.search_words, &self.search_sentences))));
+                        }
+                    }
+                }
+                Tab::Example(example) => {
+                    ui.with_layout(egui::Layout::left_to_right(egui::Align::TOP), |ui| {
+                        for word in example.words.iter() {
+                            let response = ui.label(&word.french);
+                            let Some(item) = &word.item else {
+                                continue;
+                            };
                             response.clone().on_hover_ui_at_pointer(|ui| {
                                 ui.label(format!("{}", item.tooltip()));
                             });
@@ -928,21 +1851,29 @@ impl eframe::App for App {
                             });
                         }
                     });
+                    for language in [Language::English, Language::Swedish] {
+                        ui.label(egui::RichText::new(example.skeleton(&language)).weak());
+                    }
                 }
                 Tab::PracticeView(index) => {
                     let mut to_details = None;
+                    let mut to_remove = vec![];
                     egui::Grid::new("practice_view_grid")
                         .num_columns(3)
                         .spacing([40.0, 4.0])
                         .striped(true)
                         .min_col_width((width - 80.) / 3.)
                         .show(ui, |ui| {
-                            let group = &mut self.practice_groups.groups[*index];
+                            let group = &self.practice_groups.groups[*index];
+                            let questions = if group.query.is_none() {
+                                group.questions.clone()
+                            } else {
+                                self.practice_groups.get_questions(*index, &self.search_words, &self.search_sentences)
+                            };
                             ui.label(&group.name);
                             ui.end_row();
                             
-                            let mut to_remove = vec![];
-                            for (i, question) in group.questions.iter().enumerate() {
+                            for (i, question) in questions.iter().enumerate() {
                                 let (item, is_word) = match question {
                                     QuestionTemplate::Word(uid) => {
                                         (self.search_words.get_item(*uid), true)
@@ -955,7 +1886,7 @@ impl eframe::App for App {
                                     let unknown = "Unknown".to_string();
                                     let french = item.language_string(&Language::French).unwrap_or(&unknown);
                                     let response = ui.label(french).on_hover_ui_at_pointer(|ui| { ui.label(item.tooltip()); });
-                                    if is_word {
+                                    if is_word && group.query.is_none() {
                                         response.context_menu(|ui| {
                                             if ui.button("Details").clicked() {
                                                 ui.close_menu();
@@ -977,14 +1908,26 @@ impl eframe::App for App {
                                     ui.label(item.language_string(&Language::Swedish).unwrap_or(&unknown));
                                     ui.label(item.language_string(&Language::English).unwrap_or(&unknown));
                                 } else {
-                                    ui.label("Deleted item");
+                                    ui.label("Deleted item").context_menu(|ui| {
+                                        if ui.button("Remove").clicked() {
+                                            ui.close_menu();
+                                            to_remove.push(i);
+                                        }
+                                    });
                                 }
                                 ui.end_row();
                             }
-                            for i in to_remove.iter().rev() {
-                                group.questions.remove(*i);
-                            }
                         });
+                    if !to_remove.is_empty() {
+                        let before = self.practice_groups.groups.clone();
+                        let group = &mut self.practice_groups.groups[*index];
+                        for i in to_remove.iter().rev() {
+                            group.questions.remove(*i);
+                        }
+                        let description = format!("Remove {} items from {}", to_remove.len(), group.name);
+                        self.history.record(description, vec![Change::Groups(before, self.practice_groups.groups.clone())]);
+                        self.practice_groups.save(&self.practice_groups_file);
+                    }
                     if let Some(uid) = to_details {
                         self.tab = Tab::Details(uid);
                     }
@@ -1017,7 +1960,7 @@ impl eframe::App for App {
                                             }, match part.matched[part.chosen].1.english.clone() {
                                                 None => "".to_string(),
                                                 Some(val) => val,
-                                            }, part.matched[part.chosen].1.category.clone(), "".to_string(), Some(part.matched[part.chosen].1.uid));
+                                            }, part.matched[part.chosen].1.category.clone(), part.matched[part.chosen].1.tags.join(", "), "".to_string(), part.matched[part.chosen].1.alternatives.join(", "), part.matched[part.chosen].1.ipa.clone().unwrap_or_default(), Some(part.matched[part.chosen].1.uid));
                                         }
                                         if ui.button("Delete").clicked() {
                                             ui.close_menu();
@@ -1053,7 +1996,7 @@ impl eframe::App for App {
                                                 }, match part.matched[part.chosen].1.english.clone() {
                                                     None => "".to_string(),
                                                     Some(val) => val,
-                                                }, part.matched[part.chosen].1.category.clone(), "".to_string(), Some(part.matched[part.chosen].1.uid));
+                                                }, part.matched[part.chosen].1.category.clone(), part.matched[part.chosen].1.tags.join(", "), "".to_string(), part.matched[part.chosen].1.alternatives.join(", "), part.matched[part.chosen].1.ipa.clone().unwrap_or_default(), Some(part.matched[part.chosen].1.uid));
                                             }
                                             if ui.button("Delete").clicked() {
                                                 ui.close_menu();
@@ -1100,7 +2043,7 @@ impl eframe::App for App {
                                             }, match part.matched[part.chosen].1.english.clone() {
                                                 None => "".to_string(),
                                                 Some(val) => val,
-                                            }, part.matched[part.chosen].1.category.clone(), "".to_string(), Some(part.matched[part.chosen].1.uid));
+                                            }, part.matched[part.chosen].1.category.clone(), part.matched[part.chosen].1.tags.join(", "), "".to_string(), part.matched[part.chosen].1.alternatives.join(", "), part.matched[part.chosen].1.ipa.clone().unwrap_or_default(), Some(part.matched[part.chosen].1.uid));
                                         }
                                         if ui.button("Delete").clicked() {
                                             ui.close_menu();
@@ -1110,19 +2053,26 @@ impl eframe::App for App {
                                 }
                             });
                         }
+                        let hints = explain::hints(&self.result_explain);
+                        if !hints.is_empty() {
+                            ui.separator();
+                            for hint in hints {
+                                ui.label(hint);
+                            }
+                        }
                     });
                 }
             }
             ui.with_layout(egui::Layout::bottom_up(egui::Align::LEFT), |ui| {
                 let mut change_tab: Option<Tab> = None;
                 match &self.tab {
-                    Tab::Practice(PracticeState::Wrong(_, _, _, _, item)) => {
+                    Tab::Practice(PracticeState::Wrong(_, _, _, _, item, _, _)) => {
                         let response = ui.add_sized([width, 0.], egui::Button::new("Next question"));
                         if self.popup == PopupWindow::None {
                             response.request_focus();
                         }
                         if response.clicked() {
-                            change_tab = Some(Tab::Practice(PracticeState::Question(self.practice.get_question(&self.search_words, &self.search_sentences))));
+                            change_tab = Some(Tab::Practice(question_state(self.practice.get_question(&self.search_words, &self.search_sentences))));
                             self.query_string.clear();
                         }
                         ui.add_space(ui.spacing().item_spacing.y);
@@ -1131,6 +2081,21 @@ impl eframe::App for App {
                             self.popup = PopupWindow::None;
                         }
                     }
+                    Tab::Practice(PracticeState::Question(Question { kind: QuestionKind::Choice(choices), .. })) => {
+                        let mut chosen = None;
+                        // The layout is bottom up, so go through the choices in reverse to show the first one on top
+                        for choice in choices.iter().rev() {
+                            if ui.add_sized([width, 0.], egui::Button::new(choice)).clicked() {
+                                chosen = Some(choice.clone());
+                            }
+                            ui.add_space(ui.spacing().item_spacing.y);
+                        }
+                        if let Some(choice) = chosen {
+                            self.query_string = choice;
+                            self.on_enter();
+                        }
+                    }
+                    Tab::Practice(PracticeState::Question(Question { kind: QuestionKind::Matching(..), .. })) => {}
                     Tab::Practice(PracticeState::Question(_)) => {
                         let response = ui.add_sized([width, 0.], egui::TextEdit::singleline(&mut self.query_string));
                         if response.changed() {
@@ -1177,7 +2142,7 @@ impl eframe::App for App {
                         }
                         if response.clicked() {
                             self.practice.continue_practice();
-                            change_tab = Some(Tab::Practice(PracticeState::Question(self.practice.get_question(&self.search_words, &self.search_sentences))));
+                            change_tab = Some(Tab::Practice(question_state(self.practice.get_question(&self.search_words, &self.search_sentences))));
                             self.query_string.clear();
                         }
                     }
@@ -1191,14 +2156,87 @@ impl eframe::App for App {
                                 }, match item.english.clone() {
                                     None => "".to_string(),
                                     Some(val) => val,
-                                }, item.category.clone(), "".to_string(), Some(item.uid));
+                                }, item.category.clone(), item.tags.join(", "), "".to_string(), item.alternatives.join(", "), item.ipa.clone().unwrap_or_default(), Some(item.uid));
                             }
                             if ui.button("Delete").clicked() {
                                 self.popup = PopupWindow::DeleteWord(item.uid);
                             }
+                            if let Some(french) = item.language_string(&Language::French) {
+                                if ui.button("Pronounce").clicked() {
+                                    self.pronouncer.speak_recorded(self.media.audio(&QuestionTemplate::Word(item.uid).media_name()), french);
+                                }
+                            }
+                            if ui.button("Attach media").clicked() {
+                                self.popup = PopupWindow::AttachMedia(QuestionTemplate::Word(item.uid), "".to_string(), None);
+                            }
+                            let name = QuestionTemplate::Word(item.uid).media_name();
+                            for (label, path) in [("Remove recording", self.media.audio(&name)), ("Remove picture", self.media.image(&name))] {
+                                if let Some(path) = path {
+                                    if ui.button(label).clicked() {
+                                        self.textures.remove(&path);
+                                        if let Err(err) = self.media.remove(&path) {
+                                            self.popup = PopupWindow::AttachMedia(QuestionTemplate::Word(item.uid), "".to_string(), Some(err));
+                                        }
+                                    }
+                                }
+                            }
+                        });
+                    }
+                    Tab::GenderDrill(drill) => {
+                        let mut answer = None;
+                        if let Some(noun) = gender::noun(&self.search_words, drill.current) {
+                            let (male, female) = drill.articles(&noun);
+                            ui.horizontal(|ui| {
+                                let size = [(width - ui.spacing().item_spacing.x) / 2., 0.];
+                                if ui.add_sized(size, egui::Button::new(format!("{} (←)", male))).clicked() {
+                                    answer = Some(Gender::Male);
+                                }
+                                if ui.add_sized(size, egui::Button::new(format!("{} (→)", female))).clicked() {
+                                    answer = Some(Gender::Female);
+                                }
+                            });
+                            if ui.input(|state| state.key_pressed(Key::ArrowLeft)) {
+                                answer = Some(Gender::Male);
+                            } else if ui.input(|state| state.key_pressed(Key::ArrowRight)) {
+                                answer = Some(Gender::Female);
+                            }
+                        }
+                        if let Some(gender) = answer {
+                            self.on_gender(gender);
+                        }
+                    }
+                    Tab::Conjugation(drill) => {
+                        let label = if drill.checked { "Next" } else { "Check" };
+                        if ui.add_sized([width, 0.], egui::Button::new(label)).clicked() {
+                            self.on_conjugation();
+                        }
+                    }
+                    Tab::Practice(PracticeState::Summary(_)) => {
+                        let response = ui.add_sized([width, 0.], egui::Button::new("Back to groups"));
+                        if self.popup == PopupWindow::None {
+                            response.request_focus();
+                        }
+                        if response.clicked() {
+                            change_tab = Some(Tab::PracticeSelect);
+                        }
+                    }
+                    Tab::Example(example) => {
+                        // Another sentence with the same verb
+                        ui.horizontal(|ui| {
+                            if ui.button("🔊").on_hover_text("Pronounce").clicked() {
+                                self.pronouncer.speak(&example.french());
+                            }
+                            for tense in TENSES.map(Some).into_iter().chain([None]) {
+                                let name = tense.map_or("Any tense".to_string(), |x| x.to_string());
+                                if ui.button(name).clicked() {
+                                    if let Some(example) = sentence::generate(&self.search_words, None, Some(example.verb.clone()), tense, &mut self.rng) {
+                                        change_tab = Some(Tab::Example(example));
+                                    }
+                                }
+                            }
                         });
                     }
-                    Tab::PracticeSelect | Tab::PracticeView(_) | Tab::Example(_) => {}
+                    Tab::PracticeSelect | Tab::PracticeView(_) | Tab::Sessions | Tab::Practice(PracticeState::Empty) => {}
                     _ => {
                         let response = ui.add_sized([width, 0.], egui::TextEdit::singleline(&mut self.query_string));
                         if response.changed() {
@@ -1222,7 +2260,7 @@ impl eframe::App for App {
         let mut reload = false;
         match &mut self.popup {
             PopupWindow::None => {}
-            PopupWindow::AddWord(swedish, english, ref mut category, any_verb, edit) => {
+            PopupWindow::AddWord(swedish, english, ref mut category, tags, any_verb, alternatives, ipa, edit) => {
                 egui::Window::new("Add word").resizable([false, false]).show(ctx, |ui| {
                     egui::ComboBox::from_label("Category")
                         .selected_text(format!("{}", category))
@@ -1244,10 +2282,18 @@ impl eframe::App for App {
                         ui.add(egui::TextEdit::singleline(swedish));
                         ui.label("Swedish");
                     });
+                    ui.horizontal(|ui| {
+                        ui.add(egui::TextEdit::singleline(alternatives));
+                        ui.label("Other Swedish translations");
+                    });
                     ui.horizontal(|ui| {
                         ui.add(egui::TextEdit::singleline(english));
                         ui.label("English");
                     });
+                    ui.horizontal(|ui| {
+                        ui.add(egui::TextEdit::singleline(tags));
+                        ui.label("Tags");
+                    });
                     match category {
                         Category::Noun(noun) => {
                             ui.horizontal(|ui| {
@@ -1841,6 +2887,16 @@ impl eframe::App for App {
                             }
                         }
                     }
+                    ui.horizontal(|ui| {
+                        ui.add(egui::TextEdit::singleline(ipa));
+                        ui.label("IPA");
+                        // The suggestion only follows spelling rules, so it is filled in for the user to correct
+                        if let Some((_, french)) = category.forms().0.first() {
+                            if ui.button("Suggest").on_hover_text("Guess the pronunciation from the spelling").clicked() {
+                                *ipa = phonetics::suggest(french, category);
+                            }
+                        }
+                    });
                     ui.horizontal(|ui| {
                         match edit {
                             None => {
@@ -1848,8 +2904,10 @@ impl eframe::App for App {
                                     close = true;
                                     let swedish_val = if swedish.len() > 0 { Some(swedish.clone()) } else { None };
                                     let english_val = if english.len() > 0 { Some(english.clone()) } else { None };
-                                    let uid = self.search_words.next_uid();
-                                    self.search_words.add_item(Item::new(swedish_val, english_val, category.clone(), uid));
+                                    let uid = self.search_words.new_uid();
+                                    let item = Item::new(swedish_val, english_val, category.clone(), uid, parse_tags(tags), parse_alternatives(alternatives), parse_ipa(ipa));
+                                    self.history.record(format!("Add word '{}'", item.language_string(&Language::French).unwrap()), vec![Change::AddItem(Target::Words, Box::new(item.clone()))]);
+                                    self.search_words.add_item(item).unwrap();
                                     self.search_words.save(&self.search_words_file);
                                     reload = true;
                                 }
@@ -1859,7 +2917,9 @@ impl eframe::App for App {
                                     close = true;
                                     let swedish_val = if swedish.len() > 0 { Some(swedish.clone()) } else { None };
                                     let english_val = if english.len() > 0 { Some(english.clone()) } else { None };
-                                    let item = Item::new(swedish_val, english_val, category.clone(), *uid);
+                                    let item = Item::new(swedish_val, english_val, category.clone(), *uid, parse_tags(tags), parse_alternatives(alternatives), parse_ipa(ipa));
+                                    let old = self.search_words.get_item(*uid).unwrap();
+                                    self.history.record(format!("Edit word '{}'", item.language_string(&Language::French).unwrap()), vec![Change::EditItem(Target::Words, Box::new(old), Box::new(item.clone()))]);
                                     self.search_words.edit_item(*uid, item).unwrap();
                                     self.search_words.save(&self.search_words_file);
                                     reload = true;
@@ -1872,11 +2932,12 @@ impl eframe::App for App {
                     });
                 });
             }
-            PopupWindow::AddSentence(french, swedish, english, edit) => {
+            PopupWindow::AddSentence(french, swedish, english, words, word, edit) => {
                 egui::Window::new("Add sentence").resizable([false, false]).show(ctx, |ui| {
                     ui.horizontal(|ui| {
                         if ui.add(egui::TextEdit::singleline(french)).changed() {
                             *french = french.to_lowercase();
+                            *words = explain::linked_words(french, &self.search_words);
                         }
                         ui.label("French");
                     });
@@ -1888,6 +2949,37 @@ impl eframe::App for App {
                         ui.add(egui::TextEdit::singleline(english));
                         ui.label("English");
                     });
+                    ui.label("Words");
+                    ui.horizontal_wrapped(|ui| {
+                        let mut remove = None;
+                        for (i, uid) in words.iter().enumerate() {
+                            let Some(item) = self.search_words.get_item(*uid) else {
+                                continue;
+                            };
+                            let name = item.language_string(&Language::French).cloned().unwrap_or_default();
+                            if ui.button(format!("{} ✖", name)).on_hover_text(item.tooltip()).clicked() {
+                                remove = Some(i);
+                            }
+                        }
+                        if let Some(i) = remove {
+                            words.remove(i);
+                        }
+                    });
+                    ui.horizontal(|ui| {
+                        ui.add(egui::TextEdit::singleline(word));
+                        if ui.button("Link word").clicked() {
+                            let (matches, _) = self.search_words.search_best_answers(&Query::new(word, &Language::French, 0b1111111111111111, false));
+                            if let Some((_, item)) = matches.first() {
+                                if !words.contains(&item.uid) {
+                                    words.push(item.uid);
+                                }
+                                word.clear();
+                            }
+                        }
+                        if ui.button("Relink").on_hover_text("Link the words found in the french sentence").clicked() {
+                            *words = explain::linked_words(french, &self.search_words);
+                        }
+                    });
                     ui.horizontal(|ui| {
                         match edit {
                             None => {
@@ -1895,8 +2987,11 @@ impl eframe::App for App {
                                     close = true;
                                     let swedish_val = if swedish.len() > 0 { Some(swedish.clone()) } else { None };
                                     let english_val = if english.len() > 0 { Some(english.clone()) } else { None };
-                                    let uid = self.search_sentences.next_uid();
-                                    self.search_sentences.add_item(Item::new(swedish_val, english_val, Category::Other(french.clone()), uid));
+                                    let uid = self.search_sentences.new_uid();
+                                    let mut item = Item::new(swedish_val, english_val, Category::Other(french.clone()), uid, vec![], vec![], None);
+                                    item.words = words.clone();
+                                    self.history.record(format!("Add sentence '{}'", french), vec![Change::AddItem(Target::Sentences, Box::new(item.clone()))]);
+                                    self.search_sentences.add_item(item).unwrap();
                                     self.search_sentences.save(&self.search_sentences_file);
                                     reload = true;
                                 }
@@ -1906,7 +3001,14 @@ impl eframe::App for App {
                                     close = true;
                                     let swedish_val = if swedish.len() > 0 { Some(swedish.clone()) } else { None };
                                     let english_val = if english.len() > 0 { Some(english.clone()) } else { None };
-                                    self.search_words.edit_item(*uid, Item::new(swedish_val, english_val, Category::Other(french.clone()), *uid)).unwrap();
+                                    let old = self.search_sentences.get_item(*uid).unwrap();
+                                    let mut item = old.clone();
+                                    item.swedish = swedish_val;
+                                    item.english = english_val;
+                                    item.set_category(Category::Other(french.clone()));
+                                    item.words = words.clone();
+                                    self.history.record(format!("Edit sentence '{}'", french), vec![Change::EditItem(Target::Sentences, Box::new(old), Box::new(item.clone()))]);
+                                    self.search_sentences.edit_item(*uid, item).unwrap();
                                     self.search_sentences.save(&self.search_sentences_file);
                                     reload = true;
                                 }
@@ -1921,9 +3023,21 @@ impl eframe::App for App {
             PopupWindow::DeleteWord(uid) => {
                 egui::Window::new("Delete word").resizable([false, false]).show(ctx, |ui| {
                     ui.label("Are you sure?");
+                    let groups = self.practice_groups.groups_containing(&QuestionTemplate::Word(*uid));
+                    if !groups.is_empty() {
+                        ui.label(format!("It will also be removed from {}.", groups.join(", ")));
+                    }
                     ui.horizontal(|ui| {
                         if ui.button("Delete").clicked() {
                             close = true;
+                            let item = self.search_words.get_item(*uid).unwrap();
+                            let before = self.practice_groups.groups.clone();
+                            let mut changes = vec![Change::RemoveItem(Target::Words, Box::new(item.clone()))];
+                            if self.practice_groups.remove_question(&QuestionTemplate::Word(*uid)) {
+                                changes.push(Change::Groups(before, self.practice_groups.groups.clone()));
+                                self.practice_groups.save(&self.practice_groups_file);
+                            }
+                            self.history.record(format!("Delete word '{}'", item.language_string(&Language::French).unwrap()), changes);
                             self.search_words.remove_item(*uid).unwrap();
                             self.search_words.save(&self.search_words_file);
                             reload = true;
@@ -1940,9 +3054,21 @@ impl eframe::App for App {
             PopupWindow::DeleteSentence(uid) => {
                 egui::Window::new("Delete sentence").resizable([false, false]).show(ctx, |ui| {
                     ui.label("Are you sure?");
+                    let groups = self.practice_groups.groups_containing(&QuestionTemplate::Sentence(*uid));
+                    if !groups.is_empty() {
+                        ui.label(format!("It will also be removed from {}.", groups.join(", ")));
+                    }
                     ui.horizontal(|ui| {
                         if ui.button("Delete").clicked() {
                             close = true;
+                            let item = self.search_sentences.get_item(*uid).unwrap();
+                            let before = self.practice_groups.groups.clone();
+                            let mut changes = vec![Change::RemoveItem(Target::Sentences, Box::new(item.clone()))];
+                            if self.practice_groups.remove_question(&QuestionTemplate::Sentence(*uid)) {
+                                changes.push(Change::Groups(before, self.practice_groups.groups.clone()));
+                                self.practice_groups.save(&self.practice_groups_file);
+                            }
+                            self.history.record(format!("Delete sentence '{}'", item.language_string(&Language::French).unwrap()), changes);
                             self.search_sentences.remove_item(*uid).unwrap();
                             self.search_sentences.save(&self.search_sentences_file);
                             reload = true;
@@ -1953,26 +3079,170 @@ impl eframe::App for App {
                     });
                 });
             }
-            PopupWindow::NewGroup(name, index) => {
+            PopupWindow::NewGroup(name, tags, query, exercises, settings, index) => {
                 egui::Window::new("New group").resizable([false, false]).show(ctx, |ui| {
                     ui.horizontal(|ui| {
                         ui.add(egui::TextEdit::singleline(name));
                         ui.label("Name");
                     });
+                    ui.label("Exercises");
+                    for exercise in EXERCISES {
+                        let mut checked = exercises.contains(&exercise);
+                        if ui.checkbox(&mut checked, exercise.to_string()).changed() {
+                            if checked {
+                                exercises.push(exercise);
+                            } else {
+                                exercises.retain(|x| *x != exercise);
+                            }
+                        }
+                    }
+                    ui.collapsing("Settings", |ui| {
+                        egui::ComboBox::from_label("Direction")
+                            .selected_text(settings.direction.to_string())
+                            .show_ui(ui, |ui| {
+                                for direction in DIRECTIONS {
+                                    ui.selectable_value(&mut settings.direction, direction, direction.to_string());
+                                }
+                            }
+                        );
+                        ui.horizontal(|ui| {
+                            ui.checkbox(&mut settings.singular, "Singular");
+                            ui.checkbox(&mut settings.plural, "Plural");
+                            ui.checkbox(&mut settings.male, "Male");
+                            ui.checkbox(&mut settings.female, "Female");
+                        });
+                        ui.horizontal(|ui| {
+                            for tense in TENSES {
+                                let mut checked = settings.tenses.contains(&tense);
+                                if ui.checkbox(&mut checked, tense.to_string()).changed() {
+                                    if checked {
+                                        settings.tenses.push(tense);
+                                    } else {
+                                        settings.tenses.retain(|x| *x != tense);
+                                    }
+                                }
+                            }
+                        });
+                        ui.horizontal(|ui| {
+                            for (person, checked) in PERSONS.iter().zip(settings.persons.iter_mut()) {
+                                ui.checkbox(checked, *person);
+                            }
+                        });
+                        ui.horizontal(|ui| {
+                            let mut enabled = settings.question_count.is_some();
+                            if ui.checkbox(&mut enabled, "Questions per session").changed() {
+                                settings.question_count = if enabled { Some(20) } else { None };
+                            }
+                            if let Some(count) = &mut settings.question_count {
+                                ui.add(egui::DragValue::new(count).range(1..=1000));
+                            }
+                        });
+                        ui.horizontal(|ui| {
+                            let mut enabled = settings.time_limit.is_some();
+                            if ui.checkbox(&mut enabled, "Time limit").changed() {
+                                settings.time_limit = if enabled { Some(10) } else { None };
+                            }
+                            if let Some(seconds) = &mut settings.time_limit {
+                                ui.add(egui::DragValue::new(seconds).range(1..=600));
+                            }
+                            ui.label("seconds per question");
+                        });
+                    });
+                    let mut smart = query.is_some();
+                    if ui.checkbox(&mut smart, "Smart group").changed() {
+                        *query = if smart { Some(GroupQuery::default()) } else { None };
+                    }
+                    if index.is_none() || query.is_some() {
+                        ui.horizontal(|ui| {
+                            ui.add(egui::TextEdit::singleline(tags));
+                            ui.label(if query.is_some() { "Tags" } else { "From tags" });
+                        });
+                    }
+                    if let Some(query) = query {
+                        ui.label("Categories");
+                        for (bit, label) in CATEGORY_NAMES {
+                            let mut checked = query.categories & bit != 0;
+                            if ui.checkbox(&mut checked, label).changed() {
+                                query.categories ^= bit;
+                            }
+                        }
+                        egui::ComboBox::from_label("Gender")
+                            .selected_text(query.gender.as_ref().map_or("Any".to_string(), |x| x.to_string()))
+                            .show_ui(ui, |ui| {
+                                ui.selectable_value(&mut query.gender, None, "Any");
+                                ui.selectable_value(&mut query.gender, Some(Gender::Male), "Male");
+                                ui.selectable_value(&mut query.gender, Some(Gender::Female), "Female");
+                            }
+                        );
+                        egui::ComboBox::from_label("Noun Category")
+                            .selected_text(query.noun_category.as_ref().map_or("Any".to_string(), |x| x.to_string()))
+                            .show_ui(ui, |ui| {
+                                ui.selectable_value(&mut query.noun_category, None, "Any");
+                                ui.selectable_value(&mut query.noun_category, Some(NounCategory::Object), "Object");
+                                ui.selectable_value(&mut query.noun_category, Some(NounCategory::Place), "Place");
+                                ui.selectable_value(&mut query.noun_category, Some(NounCategory::Being), "Being");
+                                ui.selectable_value(&mut query.noun_category, Some(NounCategory::Concept), "Concept");
+                            }
+                        );
+                        ui.horizontal(|ui| {
+                            let mut enabled = query.added_within_days.is_some();
+                            if ui.checkbox(&mut enabled, "Added in the last").changed() {
+                                query.added_within_days = if enabled { Some(7) } else { None };
+                            }
+                            if let Some(days) = &mut query.added_within_days {
+                                ui.add(egui::DragValue::new(days).range(1..=3650));
+                            }
+                            ui.label("days");
+                        });
+                        ui.horizontal(|ui| {
+                            let mut enabled = query.accuracy_below.is_some();
+                            if ui.checkbox(&mut enabled, "Accuracy below").changed() {
+                                query.accuracy_below = if enabled { Some(50) } else { None };
+                            }
+                            if let Some(percent) = &mut query.accuracy_below {
+                                ui.add(egui::Slider::new(percent, 0..=100).suffix("%"));
+                            }
+                        });
+                    }
                     ui.horizontal(|ui| {
                         match index {
                             None => {
                                 if ui.button("Add").clicked() {
                                     close = true;
-                                    self.practice_groups.add_group(PracticeGroup::new(name.clone()));
+                                    let before = self.practice_groups.groups.clone();
+                                    let tags = parse_tags(tags);
+                                    let mut group = if let Some(query) = query {
+                                        let mut query = query.clone();
+                                        query.tags = tags;
+                                        PracticeGroup::new_with_query(name.clone(), query)
+                                    } else if !tags.is_empty() {
+                                        let empty = "".to_string();
+                                        let questions = self.search_words.all_items(&Query::new_with_tags(&empty, &Language::French, u16::MAX, false, &tags)).iter().map(|item| QuestionTemplate::Word(item.uid)).collect();
+                                        PracticeGroup::new_with_questions(name.clone(), questions)
+                                    } else {
+                                        PracticeGroup::new(name.clone())
+                                    };
+                                    group.exercises = exercises.clone();
+                                    group.settings = settings.clone();
+                                    self.practice_groups.add_group(group);
+                                    self.history.record(format!("Add group {}", name), vec![Change::Groups(before, self.practice_groups.groups.clone())]);
                                     self.practice_groups.save(&self.practice_groups_file);
                                 }
                             }
                             Some(index) => {
                                 if ui.button("Apply").clicked() {
                                     close = true;
-                                    let group = self.practice_groups.groups.remove(*index);
-                                    self.practice_groups.add_group(PracticeGroup::new_with_questions(name.clone(), group.questions));
+                                    let before = self.practice_groups.groups.clone();
+                                    let mut group = self.practice_groups.groups.remove(*index);
+                                    group.name = name.clone();
+                                    group.query = query.clone();
+                                    group.exercises = exercises.clone();
+                                    group.settings = settings.clone();
+                                    if let Some(query) = &mut group.query {
+                                        query.tags = parse_tags(tags);
+                                    }
+                                    self.practice_groups.add_group(group);
+                                    self.history.record(format!("Edit group {}", name), vec![Change::Groups(before, self.practice_groups.groups.clone())]);
                                     self.practice_groups.save(&self.practice_groups_file);
                                 }
                             }
@@ -1989,7 +3259,9 @@ impl eframe::App for App {
                     ui.horizontal(|ui| {
                         if ui.button("Delete").clicked() {
                             close = true;
+                            let before = self.practice_groups.groups.clone();
                             self.practice_groups.remove_group(*index);
+                            self.history.record(format!("Delete group {}", before[*index].name), vec![Change::Groups(before, self.practice_groups.groups.clone())]);
                             self.practice_groups.save(&self.practice_groups_file);
                         }
                         if ui.button("Cancel").clicked() {
@@ -1998,11 +3270,481 @@ impl eframe::App for App {
                     });
                 });
             }
+            PopupWindow::BatchEdit(edit) => {
+                egui::Window::new(format!("Edit {} items", self.selected.len())).resizable([false, false]).show(ctx, |ui| {
+                    ui.horizontal(|ui| {
+                        ui.add(egui::TextEdit::singleline(&mut edit.add_tags));
+                        ui.label("Add tags");
+                    });
+                    ui.horizontal(|ui| {
+                        ui.add(egui::TextEdit::singleline(&mut edit.remove_tags));
+                        ui.label("Remove tags");
+                    });
+                    if self.tab == Tab::Words {
+                        if self.selected.iter().all(|uid| self.search_words.get_item(*uid).is_some_and(|x| x.category.single_form().is_some())) {
+                            egui::ComboBox::from_label("Category")
+                                .selected_text(edit.category.as_ref().map_or("Unchanged".to_string(), |x| x.to_string()))
+                                .show_ui(ui, |ui| {
+                                    ui.selectable_value(&mut edit.category, None, "Unchanged");
+                                    ui.selectable_value(&mut edit.category, Some(Category::Adverb("".to_string())), "Adverb");
+                                    ui.selectable_value(&mut edit.category, Some(Category::Conjunction("".to_string())), "Conjunction");
+                                    ui.selectable_value(&mut edit.category, Some(Category::Interjection("".to_string())), "Interjection");
+                                    ui.selectable_value(&mut edit.category, Some(Category::Preposition("".to_string())), "Preposition");
+                                    ui.selectable_value(&mut edit.category, Some(Category::Other("".to_string())), "Other");
+                                }
+                            );
+                        } else {
+                            ui.label("Only adverbs, conjunctions, interjections, prepositions and other words can change category together");
+                        }
+                        ui.separator();
+                        ui.label("Nouns");
+                        egui::ComboBox::from_label("Gender")
+                            .selected_text(edit.gender.as_ref().map_or("Unchanged".to_string(), |x| x.to_string()))
+                            .show_ui(ui, |ui| {
+                                ui.selectable_value(&mut edit.gender, None, "Unchanged");
+                                ui.selectable_value(&mut edit.gender, Some(Gender::Male), "Male");
+                                ui.selectable_value(&mut edit.gender, Some(Gender::Female), "Female");
+                            }
+                        );
+                        egui::ComboBox::from_label("Countability")
+                            .selected_text(edit.countable.as_ref().map_or("Unchanged".to_string(), |x| x.to_string()))
+                            .show_ui(ui, |ui| {
+                                ui.selectable_value(&mut edit.countable, None, "Unchanged");
+                                ui.selectable_value(&mut edit.countable, Some(Countability::Countable), "Countable");
+                                ui.selectable_value(&mut edit.countable, Some(Countability::Uncountable), "Uncountable");
+                            }
+                        );
+                        egui::ComboBox::from_label("Concreteness")
+                            .selected_text(edit.concrete.as_ref().map_or("Unchanged".to_string(), |x| x.to_string()))
+                            .show_ui(ui, |ui| {
+                                ui.selectable_value(&mut edit.concrete, None, "Unchanged");
+                                ui.selectable_value(&mut edit.concrete, Some(Concreteness::Concrete), "Concrete");
+                                ui.selectable_value(&mut edit.concrete, Some(Concreteness::Abstract), "Abstract");
+                            }
+                        );
+                        egui::ComboBox::from_label("Properness")
+                            .selected_text(edit.proper.as_ref().map_or("Unchanged".to_string(), |x| x.to_string()))
+                            .show_ui(ui, |ui| {
+                                ui.selectable_value(&mut edit.proper, None, "Unchanged");
+                                ui.selectable_value(&mut edit.proper, Some(ProperOrCommon::Common), "Common");
+                                ui.selectable_value(&mut edit.proper, Some(ProperOrCommon::Proper), "Proper");
+                            }
+                        );
+                        egui::ComboBox::from_label("Noun Category")
+                            .selected_text(edit.noun_category.as_ref().map_or("Unchanged".to_string(), |x| x.to_string()))
+                            .show_ui(ui, |ui| {
+                                ui.selectable_value(&mut edit.noun_category, None, "Unchanged");
+                                ui.selectable_value(&mut edit.noun_category, Some(NounCategory::Object), "Object");
+                                ui.selectable_value(&mut edit.noun_category, Some(NounCategory::Place), "Place");
+                                ui.selectable_value(&mut edit.noun_category, Some(NounCategory::Being), "Being");
+                                ui.selectable_value(&mut edit.noun_category, Some(NounCategory::Concept), "Concept");
+                            }
+                        );
+                    }
+                    ui.horizontal(|ui| {
+                        if ui.button("Apply").clicked() {
+                            close = true;
+                            let (search, file) = if self.tab == Tab::Words { (&mut self.search_words, &self.search_words_file) } else { (&mut self.search_sentences, &self.search_sentences_file) };
+                            let target = if self.tab == Tab::Words { Target::Words } else { Target::Sentences };
+                            let mut changes = vec![];
+                            for uid in &self.selected {
+                                if let Some(item) = search.get_item(*uid) {
+                                    let new = edit.apply(item.clone());
+                                    search.edit_item(*uid, new.clone()).unwrap();
+                                    changes.push(Change::EditItem(target, Box::new(item), Box::new(new)));
+                                }
+                            }
+                            self.history.record(format!("Edit {} items", changes.len()), changes);
+                            search.save(file);
+                            reload = true;
+                        }
+                        if ui.button("Cancel").clicked() {
+                            close = true;
+                        }
+                    });
+                });
+            }
+            PopupWindow::DeleteSelection => {
+                egui::Window::new(format!("Delete {} items", self.selected.len())).resizable([false, false]).show(ctx, |ui| {
+                    ui.label("Are you sure? The items will also be removed from any practice groups.");
+                    ui.horizontal(|ui| {
+                        if ui.button("Delete").clicked() {
+                            close = true;
+                            let (search, file) = if self.tab == Tab::Words { (&mut self.search_words, &self.search_words_file) } else { (&mut self.search_sentences, &self.search_sentences_file) };
+                            let target = if self.tab == Tab::Words { Target::Words } else { Target::Sentences };
+                            let before = self.practice_groups.groups.clone();
+                            let mut groups_changed = false;
+                            let mut changes = vec![];
+                            for uid in &self.selected {
+                                if let Some(item) = search.get_item(*uid) {
+                                    search.remove_item(*uid).unwrap();
+                                    changes.push(Change::RemoveItem(target, Box::new(item)));
+                                    let question = if target == Target::Words { QuestionTemplate::Word(*uid) } else { QuestionTemplate::Sentence(*uid) };
+                                    groups_changed |= self.practice_groups.remove_question(&question);
+                                }
+                            }
+                            let description = format!("Delete {} items", changes.len());
+                            if groups_changed {
+                                changes.push(Change::Groups(before, self.practice_groups.groups.clone()));
+                                self.practice_groups.save(&self.practice_groups_file);
+                            }
+                            self.history.record(description, changes);
+                            search.save(file);
+                            self.selected.clear();
+                            reload = true;
+                        }
+                        if ui.button("Cancel").clicked() {
+                            close = true;
+                        }
+                    });
+                });
+            }
+            PopupWindow::Conjugation(group, tenses, filter, empty) => {
+                egui::Window::new("Conjugation drill").resizable([false, false]).show(ctx, |ui| {
+                    for tense in TENSES {
+                        let mut checked = tenses.contains(&tense);
+                        if ui.checkbox(&mut checked, tense.to_string()).changed() {
+                            if checked {
+                                tenses.push(tense);
+                            } else {
+                                tenses.retain(|x| *x != tense);
+                            }
+                        }
+                    }
+                    ui.separator();
+                    for option in [VerbFilter::All, VerbFilter::Regular, VerbFilter::Irregular] {
+                        ui.radio_value(filter, option, option.to_string());
+                    }
+                    if *empty {
+                        ui.label("There are no verbs with these forms.");
+                    }
+                    ui.horizontal(|ui| {
+                        if ui.add_enabled(!tenses.is_empty(), egui::Button::new("Start")).clicked() {
+                            let uids = match group {
+                                Some(index) => self.practice_groups.get_questions(*index, &self.search_words, &self.search_sentences).into_iter().filter_map(|x| match x {
+                                    QuestionTemplate::Word(uid) => Some(uid),
+                                    QuestionTemplate::Sentence(_) => None,
+                                }).collect(),
+                                None => self.search_words.iter().map(|x| x.uid).collect(),
+                            };
+                            match ConjugationDrill::new(&self.search_words, uids, tenses.clone(), *filter, &mut self.rng) {
+                                Some(drill) => {
+                                    close = true;
+                                    change_tab = Some(Tab::Conjugation(drill));
+                                }
+                                None => *empty = true,
+                            }
+                        }
+                        if ui.button("Cancel").clicked() {
+                            close = true;
+                        }
+                    });
+                });
+            }
+            PopupWindow::CheckGroups => {
+                egui::Window::new("Check groups").resizable([false, false]).show(ctx, |ui| {
+                    let dangling = self.practice_groups.dangling(&self.search_words, &self.search_sentences);
+                    let dangling_accuracy = self.practice_groups.dangling_accuracy(&self.search_words, &self.search_sentences);
+                    if dangling.is_empty() && dangling_accuracy == 0 {
+                        ui.label("All practice groups are fine.");
+                    }
+                    for (index, count) in &dangling {
+                        ui.label(format!("{} has {} deleted items", self.practice_groups.groups[*index].name, count));
+                    }
+                    if dangling_accuracy != 0 {
+                        ui.label(format!("{} deleted items have practice statistics", dangling_accuracy));
+                    }
+                    ui.horizontal(|ui| {
+                        if ui.add_enabled(!dangling.is_empty() || dangling_accuracy != 0, egui::Button::new("Repair")).clicked() {
+                            close = true;
+                            let before = self.practice_groups.groups.clone();
+                            self.practice_groups.repair(&self.search_words, &self.search_sentences);
+                            self.history.record("Repair groups".to_string(), vec![Change::Groups(before, self.practice_groups.groups.clone())]);
+                            self.practice_groups.save(&self.practice_groups_file);
+                        }
+                        if ui.button("Close").clicked() {
+                            close = true;
+                        }
+                    });
+                });
+            }
+            PopupWindow::Merge(state) => {
+                let target = if self.tab == Tab::Words { Target::Words } else { Target::Sentences };
+                let mut next_state = None;
+                egui::Window::new("Merge").resizable([false, true]).show(ctx, |ui| {
+                    match state {
+                        MergeState::Files(theirs, base, practice, error) => {
+                            ui.horizontal(|ui| {
+                                ui.add(egui::TextEdit::singleline(theirs));
+                                ui.label("File to merge");
+                            });
+                            ui.horizontal(|ui| {
+                                ui.add(egui::TextEdit::singleline(base));
+                                ui.label("Common ancestor (optional)");
+                            });
+                            ui.horizontal(|ui| {
+                                ui.add(egui::TextEdit::singleline(practice));
+                                ui.label("Practice groups (optional)");
+                            });
+                            if let Some(error) = error {
+                                ui.label(error.as_str());
+                            }
+                            ui.horizontal(|ui| {
+                                if ui.button("Compare").clicked() {
+                                    let search = if target == Target::Words { &self.search_words } else { &self.search_sentences };
+                                    let base_search = if base.is_empty() { Ok(None) } else { Search::load(base).map(|(x, _)| Some(x)) };
+                                    match (Search::load(theirs), base_search) {
+                                        (Ok((other, legacy)), Ok(base)) => {
+                                            // Practice groups as old as the file being merged refer to its items by their old uids
+                                            let (legacy, none) = (legacy.unwrap_or_default(), LegacyUids::new());
+                                            let (words, sentences) = if target == Target::Words { (&legacy, &none) } else { (&none, &legacy) };
+                                            match if practice.is_empty() { Ok(None) } else { PracticeGroupCollection::load(practice, words, sentences).map(Some) } {
+                                                Ok(practice_groups) => next_state = Some(MergeState::Resolve(Box::new(merge(search, &other, base.as_ref())), theirs.clone(), practice_groups)),
+                                                Err(x) => *error = Some(x),
+                                            }
+                                        }
+                                        (Err(x), _) | (_, Err(x)) => *error = Some(x),
+                                    }
+                                }
+                                if ui.button("Cancel").clicked() {
+                                    close = true;
+                                }
+                            });
+                        }
+                        MergeState::Resolve(merge, theirs, practice) => {
+                            ui.label(format!("{} new items, {} updated items, {} removed items", merge.added.len(), merge.updated.len(), merge.removed.len()));
+                            if let Some(practice) = practice {
+                                ui.label(format!("{} practice groups", practice.groups.len()));
+                            }
+                            if !merge.conflicts.is_empty() {
+                                ui.separator();
+                                ui.heading("Conflicts");
+                                egui::ScrollArea::vertical().max_height(400.0).show(ui, |ui| {
+                                    egui::Grid::new("merge_grid")
+                                        .num_columns(2)
+                                        .spacing([40.0, 4.0])
+                                        .striped(true)
+                                        .show(ui, |ui| {
+                                            ui.label("Ours");
+                                            ui.label("Theirs");
+                                            ui.end_row();
+                                            for conflict in &mut merge.conflicts {
+                                                ui.radio_value(&mut conflict.resolution, Resolution::Ours, conflict.ours.tooltip());
+                                                ui.radio_value(&mut conflict.resolution, Resolution::Theirs, conflict.theirs.tooltip());
+                                                ui.end_row();
+                                            }
+                                        });
+                                });
+                            }
+                            if !merge.deletions.is_empty() {
+                                ui.separator();
+                                ui.heading("Deleted on one side, changed on the other");
+                                egui::ScrollArea::vertical().id_salt("merge_deletions").max_height(400.0).show(ui, |ui| {
+                                    egui::Grid::new("merge_deletions_grid")
+                                        .num_columns(2)
+                                        .spacing([40.0, 4.0])
+                                        .striped(true)
+                                        .show(ui, |ui| {
+                                            ui.label("Ours");
+                                            ui.label("Theirs");
+                                            ui.end_row();
+                                            for deletion in &mut merge.deletions {
+                                                ui.radio_value(&mut deletion.resolution, Resolution::Ours, deletion.ours.as_ref().map_or("Deleted".to_string(), |x| x.tooltip()));
+                                                ui.radio_value(&mut deletion.resolution, Resolution::Theirs, deletion.theirs.as_ref().map_or("Deleted".to_string(), |x| x.tooltip()));
+                                                ui.end_row();
+                                            }
+                                        });
+                                });
+                            }
+                            ui.horizontal(|ui| {
+                                if ui.button("Apply").clicked() {
+                                    close = true;
+                                    let (search, file) = if target == Target::Words { (&mut self.search_words, &self.search_words_file) } else { (&mut self.search_sentences, &self.search_sentences_file) };
+                                    let before = self.practice_groups.groups.clone();
+                                    let mut changes = vec![];
+                                    // Taking their side of a deletion conflict either brings their item back or deletes ours
+                                    let deletions: Vec<_> = merge.deletions.iter().filter(|x| x.resolution == Resolution::Theirs).collect();
+                                    for item in merge.added.iter().chain(deletions.iter().filter_map(|x| x.theirs.as_ref())) {
+                                        search.add_item(item.clone()).unwrap();
+                                        changes.push(Change::AddItem(target, Box::new(item.clone())));
+                                    }
+                                    let updated = merge.updated.iter().map(|(ours, theirs)| (ours, theirs));
+                                    let resolved = merge.conflicts.iter().filter(|x| x.resolution == Resolution::Theirs).map(|x| (&x.ours, &x.theirs));
+                                    for (ours, theirs) in updated.chain(resolved) {
+                                        search.edit_item(ours.uid, theirs.clone()).unwrap();
+                                        changes.push(Change::EditItem(target, Box::new(ours.clone()), Box::new(theirs.clone())));
+                                    }
+                                    for item in merge.removed.iter().chain(deletions.iter().filter_map(|x| x.ours.as_ref())) {
+                                        search.remove_item(item.uid).unwrap();
+                                        changes.push(Change::RemoveItem(target, Box::new(item.clone())));
+                                        let question = if target == Target::Words { QuestionTemplate::Word(item.uid) } else { QuestionTemplate::Sentence(item.uid) };
+                                        self.practice_groups.remove_question(&question);
+                                    }
+                                    if let Some(other) = practice.take() {
+                                        self.practice_groups.import(other, &merge.remap, target == Target::Words, &self.search_words, &self.search_sentences);
+                                    }
+                                    if self.practice_groups.groups != before {
+                                        changes.push(Change::Groups(before, self.practice_groups.groups.clone()));
+                                        self.practice_groups.save(&self.practice_groups_file);
+                                    }
+                                    self.history.record(format!("Merge {}", theirs), changes);
+                                    let search = if target == Target::Words { &self.search_words } else { &self.search_sentences };
+                                    search.save(file);
+                                    reload = true;
+                                }
+                                if ui.button("Cancel").clicked() {
+                                    close = true;
+                                }
+                            });
+                        }
+                    }
+                });
+                if let Some(next_state) = next_state {
+                    *state = next_state;
+                }
+            }
+            PopupWindow::Duplicates(groups) => {
+                let target = if self.tab == Tab::Words { Target::Words } else { Target::Sentences };
+                let template = |uid: Uid| if target == Target::Words { QuestionTemplate::Word(uid) } else { QuestionTemplate::Sentence(uid) };
+                egui::Window::new("Duplicates").resizable([false, true]).show(ctx, |ui| {
+                    let count = groups.len();
+                    match groups.first_mut() {
+                        None => {
+                            ui.label("No duplicates found.");
+                        }
+                        Some(duplicates) => {
+                            ui.label(format!("{} groups of possible duplicates", count));
+                            egui::ScrollArea::vertical().max_height(400.0).show(ui, |ui| {
+                                egui::Grid::new("duplicates_grid")
+                                    .num_columns(5)
+                                    .spacing([40.0, 4.0])
+                                    .striped(true)
+                                    .show(ui, |ui| {
+                                        ui.label("Item");
+                                        ui.label("Keep");
+                                        ui.label("Forms");
+                                        ui.label("Swedish");
+                                        ui.label("English");
+                                        ui.end_row();
+                                        for i in 0..duplicates.items.len() {
+                                            let item = duplicates.items[i].clone();
+                                            ui.label(item.language_string(&Language::French).cloned().unwrap_or_default()).on_hover_text(item.tooltip());
+                                            ui.radio_value(&mut duplicates.keep, i, "");
+                                            ui.radio_value(&mut duplicates.category, i, "");
+                                            ui.radio_value(&mut duplicates.swedish, i, item.swedish.as_deref().unwrap_or("-"));
+                                            ui.radio_value(&mut duplicates.english, i, item.english.as_deref().unwrap_or("-"));
+                                            ui.end_row();
+                                        }
+                                    });
+                            });
+                            ui.separator();
+                            ui.label(format!("Result: {}", duplicates.merged().tooltip()));
+                        }
+                    }
+                    ui.horizontal(|ui| {
+                        if ui.add_enabled(!groups.is_empty(), egui::Button::new("Merge")).clicked() {
+                            let duplicates = groups.remove(0);
+                            let merged = duplicates.merged();
+                            let kept = &duplicates.items[duplicates.keep];
+                            let (search, file) = if target == Target::Words { (&mut self.search_words, &self.search_words_file) } else { (&mut self.search_sentences, &self.search_sentences_file) };
+                            let before = self.practice_groups.groups.clone();
+                            search.edit_item(kept.uid, merged.clone()).unwrap();
+                            let mut changes = vec![Change::EditItem(target, Box::new(kept.clone()), Box::new(merged.clone()))];
+                            for (i, item) in duplicates.items.iter().enumerate() {
+                                if i != duplicates.keep {
+                                    search.remove_item(item.uid).unwrap();
+                                    changes.push(Change::RemoveItem(target, Box::new(item.clone())));
+                                    self.practice_groups.redirect(&template(item.uid), &template(kept.uid));
+                                    self.selected.retain(|x| *x != item.uid);
+                                }
+                            }
+                            if target == Target::Words {
+                                for (i, item) in duplicates.items.iter().enumerate() {
+                                    if i != duplicates.keep {
+                                        for (old, new) in self.search_sentences.redirect_words(item.uid, kept.uid) {
+                                            changes.push(Change::EditItem(Target::Sentences, Box::new(old), Box::new(new)));
+                                        }
+                                    }
+                                }
+                            }
+                            if self.practice_groups.groups != before {
+                                changes.push(Change::Groups(before, self.practice_groups.groups.clone()));
+                            }
+                            self.history.record(format!("Merge duplicates of {}", merged.language_string(&Language::French).cloned().unwrap_or_default()), changes);
+                            let search = if target == Target::Words { &self.search_words } else { &self.search_sentences };
+                            search.save(file);
+                            if target == Target::Words {
+                                self.search_sentences.save(&self.search_sentences_file);
+                            }
+                            self.practice_groups.save(&self.practice_groups_file);
+                            reload = true;
+                        }
+                        if ui.add_enabled(!groups.is_empty(), egui::Button::new("Skip")).clicked() {
+                            groups.remove(0);
+                        }
+                        if ui.button("Close").clicked() {
+                            close = true;
+                        }
+                    });
+                });
+            }
+            PopupWindow::AttachMedia(template, file, error) => {
+                egui::Window::new("Attach media").resizable([false, false]).show(ctx, |ui| {
+                    ui.label("A wav file is used as the recording, a png or jpg file as the picture.");
+                    ui.horizontal(|ui| {
+                        ui.add(egui::TextEdit::singleline(file));
+                        ui.label("File");
+                    });
+                    if let Some(error) = error.as_ref() {
+                        ui.colored_label(egui::Color32::RED, error);
+                    }
+                    ui.horizontal(|ui| {
+                        if ui.button("Attach").clicked() {
+                            match self.media.attach(&template.media_name(), file) {
+                                Ok(_) => {
+                                    // The old picture may have had another extension, so forget every cached one
+                                    self.textures.clear();
+                                    close = true;
+                                }
+                                Err(err) => *error = Some(err),
+                            }
+                        }
+                        if ui.button("Cancel").clicked() {
+                            close = true;
+                        }
+                    });
+                });
+            }
+            PopupWindow::Export(file) => {
+                egui::Window::new(format!("Export {} items", self.selected.len())).resizable([false, false]).show(ctx, |ui| {
+                    ui.horizontal(|ui| {
+                        ui.add(egui::TextEdit::singleline(file));
+                        ui.label("File");
+                    });
+                    ui.horizontal(|ui| {
+                        if ui.button("Export").clicked() {
+                            close = true;
+                            let search = if self.tab == Tab::Words { &self.search_words } else { &self.search_sentences };
+                            search.subset(&self.selected).save(file);
+                        }
+                        if ui.button("Cancel").clicked() {
+                            close = true;
+                        }
+                    });
+                });
+            }
         }
         if close {
             self.popup = PopupWindow::None;
         }
+        if let Some((french, index)) = matching_click {
+            self.on_matching(french, index);
+        }
+        if timed_out {
+            self.on_enter();
+        }
         if reload {
+            self.refresh_validation();
             self.gen_results();
         }
         if let Some(tab) = change_tab {
@@ -2014,11 +3756,11 @@ impl eframe::App for App {
     }
 }
 
-pub fn run(search_words: Search, search_sentences: Search, practice_groups: PracticeGroupCollection, search_words_file: String, search_sentences_file: String, practice_file: String) -> eframe::Result<()> {
+pub fn run(search_words: Search, search_sentences: Search, practice_groups: PracticeGroupCollection, search_words_file: String, search_sentences_file: String, practice_file: String, pronouncer: Pronouncer) -> eframe::Result<()> {
     let native_options = eframe::NativeOptions::default();
     eframe::run_native(
         "French",
         native_options,
-        Box::new(|cc| Ok(Box::new(App::new(cc, search_words, search_sentences, practice_groups, search_words_file, search_sentences_file, practice_file)))),
+        Box::new(|_cc| Ok(Box::new(App::new(search_words, search_sentences, practice_groups, search_words_file, search_sentences_file, practice_file, pronouncer)))),
     )
 }
\ No newline at end of file
@@ -1,3 +1,5 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
 pub fn get_adjective_plural(string: &str) -> String {
     if string.ends_with("al") || string.ends_with("au") {
         string[0..string.len()-2].to_string() + "aux"
@@ -12,6 +14,22 @@ pub fn get_adjective_plural(string: &str) -> String {
     }
 }
 
+pub fn get_adjective_feminine(string: &str) -> String {
+    if string.ends_with("e") {
+        string.to_string()
+    } else if string.ends_with("eux") {
+        string[0..string.len()-1].to_string() + "se"
+    } else if string.ends_with("if") {
+        string[0..string.len()-1].to_string() + "ve"
+    } else if string.ends_with("er") {
+        string[0..string.len()-2].to_string() + "ère"
+    } else if string.ends_with("el") || string.ends_with("en") || string.ends_with("on") || string.ends_with("et") {
+        string.to_string() + &string[string.len()-1..] + "e"
+    } else {
+        string.to_string() + "e"
+    }
+}
+
 pub fn number_forms(cardinal: &str) -> (String, String) {
     let ordinal = if cardinal.ends_with("e") {
         cardinal[0..cardinal.len()-1].to_string() + "ième"
@@ -26,4 +44,42 @@ pub fn number_forms(cardinal: &str) -> (String, String) {
         cardinal.to_string() + "aine"
     };
     (ordinal, approximate)
-}
\ No newline at end of file
+}
+
+pub fn now() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|x| x.as_secs()).unwrap_or(0)
+}
+// Formats seconds since the epoch as a UTC date like 2024-03-05
+pub fn date(seconds: u64) -> String {
+    // Days to civil date, see http://howardhinnant.github.io/date_algorithms.html
+    let days = (seconds / 86400) as i64 + 719468;
+    let era = days.div_euclid(146097);
+    let day_of_era = days.rem_euclid(146097);
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+    format!("{}-{:02}-{:02}", year, month, day)
+}
+
+pub fn duration(seconds: u64) -> String {
+    format!("{}:{:02}", seconds / 60, seconds % 60)
+}
+
+// Saved files start with this and the version of their layout, so the layout is known before decoding.
+// Files from the first release have no header. They start with the length of a list, which can never read as this.
+pub const MAGIC: &[u8] = b"FRENCH";
+pub const FORMAT_VERSION: u16 = 1;
+
+pub fn with_header(data: Vec<u8>) -> Vec<u8> {
+    [MAGIC, &FORMAT_VERSION.to_le_bytes(), &data].concat()
+}
+
+// The version and the data after the header, or None for a file from the first release
+pub fn split_header(data: &[u8]) -> Option<(u16, &[u8])> {
+    let (version, data) = data.strip_prefix(MAGIC)?.split_first_chunk::<2>()?;
+    Some((u16::from_le_bytes(*version), data))
+}
+
@@ -0,0 +1,93 @@
+use std::fmt::Display;
+use crate::{search::{Adjective, Category, Countability, Item, Language, ProperOrCommon, Search, Uid}, utils};
+
+#[derive(PartialEq)]
+pub enum Issue {
+    EmptyForm(&'static str),
+    MissingTranslation(Language),
+    SuspiciousForm(&'static str, String),
+    Whitespace(&'static str),
+}
+
+impl Display for Issue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::EmptyForm(form) => write!(f, "Empty {}", form),
+            Self::MissingTranslation(language) => write!(f, "Missing {} translation", language),
+            Self::SuspiciousForm(form, expected) => write!(f, "Unexpected {}, expected '{}'", form, expected),
+            Self::Whitespace(form) => write!(f, "Stray whitespace in {}", form),
+        }
+    }
+}
+
+#[derive(PartialEq)]
+pub struct Finding {
+    pub uid: Uid,
+    pub french: String,
+    pub issue: Issue,
+}
+
+// Forms that disagree with the regular inflection rules, regular variants ending in -s are always accepted
+fn suspicious(category: &Category) -> Vec<(&'static str, String)> {
+    let mut found = vec![];
+    match category {
+        Category::Noun(noun) if noun.countable == Countability::Countable && noun.proper == ProperOrCommon::Common && !noun.plural.is_empty() => {
+            let expected = utils::get_adjective_plural(&noun.singular);
+            if noun.plural != expected && noun.plural != noun.singular.clone() + "s" {
+                found.push(("plural", expected));
+            }
+        }
+        Category::Adjective(Adjective::Descriptive(male, female, plural_male, plural_female)) => {
+            let expected = utils::get_adjective_feminine(male);
+            if !female.is_empty() && *female != expected && *female != male.clone() + "e" {
+                found.push(("singular female", expected));
+            }
+            let expected = utils::get_adjective_plural(male);
+            if !plural_male.is_empty() && *plural_male != expected && *plural_male != male.clone() + "s" {
+                found.push(("plural male", expected));
+            }
+            let expected = utils::get_adjective_plural(female);
+            if !plural_female.is_empty() && *plural_female != expected && *plural_female != female.clone() + "s" {
+                found.push(("plural female", expected));
+            }
+        }
+        _ => {}
+    }
+    found
+}
+
+fn validate_item(item: &Item) -> Vec<Issue> {
+    let mut issues = vec![];
+    let (required, optional) = item.category.forms();
+    for (name, form) in &required {
+        if form.trim().is_empty() {
+            issues.push(Issue::EmptyForm(name));
+        }
+    }
+    // Practice asks for the swedish translation, the english one is optional
+    if item.swedish.as_ref().is_none_or(|x| x.trim().is_empty()) {
+        issues.push(Issue::MissingTranslation(Language::Swedish));
+    }
+    let translations = item.swedish.iter().map(|x| ("swedish", x)).chain(item.english.iter().map(|x| ("english", x)));
+    for (name, form) in required.into_iter().chain(optional).chain(translations) {
+        if !form.trim().is_empty() && (form.trim() != form || form.contains("  ")) {
+            issues.push(Issue::Whitespace(name));
+        }
+    }
+    for (name, expected) in suspicious(&item.category) {
+        issues.push(Issue::SuspiciousForm(name, expected));
+    }
+    issues
+}
+
+pub fn validate(search: &Search) -> Vec<Finding> {
+    let mut findings = vec![];
+    for item in search.iter() {
+        let french = item.language_string(&Language::French).cloned().unwrap_or_default();
+        for issue in validate_item(item) {
+            findings.push(Finding { uid: item.uid, french: french.clone(), issue });
+        }
+    }
+    findings.sort_by(|a, b| a.french.cmp(&b.french));
+    findings
+}